@@ -5,16 +5,192 @@ pub const VERSION_MAJOR: u32 = 1;
 #[coast]
 pub const VERSION_MINOR: u32 = 0;
 #[coast]
+pub const VERSION_NUMBER: u32 = VERSION_MAJOR << 16 | VERSION_MINOR;
+#[coast]
 pub const VERSION: &str = "1.0";
+#[coast]
+pub const KEY_LEN: usize = 4;
 
 #[coast]
 pub fn add(a: i32, b: i32) -> i32 {
     a + b
 }
 
+#[coast(doc = "Divides a by b, rounding towards zero.")]
+pub fn divide(a: i32, b: i32) -> i32 {
+    a / b
+}
+
+#[coast(panic = "error", error_value = -1)]
+pub fn factorial(n: u32) -> i64 {
+    (1..=i64::from(n)).fold(1, |f, i| f.checked_mul(i).expect("factorial overflowed"))
+}
+
+/// Counts the characters, rather than the bytes, in `text`.
+#[coast]
+pub fn char_count(#[coast(len)] text: &str) -> usize {
+    text.chars().count()
+}
+
+/// Counts the words in `text`, replacing any invalid UTF-8.
+#[coast]
+pub fn word_count(#[coast(invalid_utf8 = "lossy")] text: Option<&str>) -> usize {
+    text.map_or(0, |text| text.split_whitespace().count())
+}
+
+#[coast]
+pub fn byte_count(text: &std::ffi::CStr) -> usize {
+    text.to_bytes().len()
+}
+
+/// Returns a greeting for `name`, or for everyone if there's no name.
+#[coast]
+pub fn greeting(name: Option<&str>) -> String {
+    format!("Hello, {}!", name.unwrap_or("world"))
+}
+
+#[coast]
+pub fn version_string() -> &'static str {
+    VERSION
+}
+
+/// Adds up `values` without overflowing.
+///
+/// ```
+/// assert_eq!(basic::sum(&[i32::MAX, 1]), 1 << 31);
+/// ```
+#[coast]
+pub fn sum(values: &[i32]) -> i64 {
+    values.iter().copied().map(i64::from).sum()
+}
+
+/// Adds up `values`.
+#[coast(
+    deprecated = "use basic_sum() instead",
+    since = "0.9",
+    name = "basic_total"
+)]
+pub fn total(values: &[i32]) -> i64 {
+    sum(values)
+}
+
+/// Multiplies each value by `factor` in place.
+#[coast]
+pub fn scale(values: &mut [f64], factor: f64) {
+    values.iter_mut().for_each(|value| *value *= factor);
+}
+
+/// Returns the primes below `limit`.
+#[coast]
+pub fn primes(limit: u32) -> Vec<u32> {
+    (2..limit)
+        .filter(|n| (2..*n).take_while(|d| d * d <= *n).all(|d| n % d != 0))
+        .collect()
+}
+
+/// Encrypts or decrypts `data` in place with a key of [`KEY_LEN`] bytes, very insecurely.
+#[coast]
+pub fn xor_cipher(key: &[u8; KEY_LEN], data: &mut [u8]) {
+    data.iter_mut()
+        .zip(key.iter().cycle())
+        .for_each(|(byte, key)| *byte ^= key);
+}
+
+/// Counts from 1 to `limit`, reporting each number to C.
+#[coast]
+pub fn count_up(
+    limit: u32,
+    /// Called with each number.
+    report: extern "C" fn(u32),
+) {
+    for n in 1..=limit {
+        report(n);
+    }
+}
+
+/// Limits `value` to the bounds that are given.
+///
+/// # Arguments
+///
+/// * `min` - The lower bound, if any.
+/// * `max` - The upper bound, if any.
+#[coast]
+pub fn clamp(value: i32, min: Option<i32>, #[coast(nullable)] max: Option<i32>) -> i32 {
+    let value = min.map_or(value, |min| value.max(min));
+    max.map_or(value, |max| value.min(max))
+}
+
+/// Returns the smallest and largest values, which are infinite if there are none.
+#[coast(out = "min, max")]
+pub fn min_max(values: &[f64]) -> (f64, f64) {
+    values
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &value| {
+            (min.min(value), max.max(value))
+        })
+}
+
+/// Interpolates between `a` and `b`, where `t` is from 0 to 1.
+#[coast(
+    instantiate(T = f32, suffix = "_f32"),
+    instantiate(T = f64, suffix = "_f64")
+)]
+pub fn lerp<T>(a: T, b: T, t: T) -> T
+where
+    T: Copy + std::ops::Add<Output = T> + std::ops::Sub<Output = T> + std::ops::Mul<Output = T>,
+{
+    a + (b - a) * t
+}
+
+/// Returns the largest of `values`, if there are any.
+#[coast(
+    instantiate(T = i32, suffix = "_i32"),
+    instantiate(T = f64, suffix = "_f64")
+)]
+pub fn largest<T: PartialOrd + Copy>(values: &[T]) -> Option<T> {
+    values
+        .iter()
+        .copied()
+        .reduce(|max, value| if value > max { value } else { max })
+}
+
+/// Sets `len` bytes at `buffer` to `value`, like `memset`.
+///
+/// # Safety
+///
+/// `buffer` must be valid for writes of `len` bytes.
+#[coast]
+pub unsafe fn fill(#[coast(c_type = "void*")] buffer: *mut u8, len: usize, value: u8) {
+    unsafe { std::ptr::write_bytes(buffer, value, len) };
+}
+
 coastal::api! {
+    prefix = "basic";
+    header = "../target/include/basic.h";
+    panic = "last_error";
+
     const VERSION_MAJOR;
     const VERSION_MINOR;
+    const VERSION_NUMBER;
     const VERSION;
+    const KEY_LEN;
     fn add;
+    fn divide;
+    fn factorial;
+    fn char_count;
+    fn word_count;
+    fn byte_count;
+    fn greeting;
+    fn version_string;
+    fn sum;
+    fn total;
+    fn scale;
+    fn primes;
+    fn xor_cipher;
+    fn count_up;
+    fn fill;
+    fn clamp;
+    fn min_max;
+    fn lerp;
+    fn largest;
 }
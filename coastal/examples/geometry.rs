@@ -0,0 +1,249 @@
+use coastal::coast;
+
+#[coast]
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[coast]
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Rect {
+    pub origin: Point,
+    pub size: Point,
+}
+
+#[coast(error)]
+#[derive(Debug, Clone, Copy)]
+pub enum Error {
+    NegativeSize = 1,
+    Empty,
+    OutOfRange,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            Error::NegativeSize => "size must not be negative",
+            Error::Empty => "polyline is empty",
+            Error::OutOfRange => "index is out of range",
+        })
+    }
+}
+
+#[coast]
+pub fn rect_new(origin: Point, width: f64, height: f64) -> Result<Rect, Error> {
+    if width < 0.0 || height < 0.0 {
+        return Err(Error::NegativeSize);
+    }
+    Ok(Rect {
+        origin,
+        size: Point {
+            x: width,
+            y: height,
+        },
+    })
+}
+
+#[coast]
+pub fn rect_area(rect: Rect) -> f64 {
+    rect.size.x * rect.size.y
+}
+
+#[coast]
+pub fn rect_centre(rect: Rect) -> Point {
+    Point {
+        x: rect.origin.x + rect.size.x / 2.0,
+        y: rect.origin.y + rect.size.y / 2.0,
+    }
+}
+
+#[coast]
+pub fn triangle_area(corners: [Point; 3]) -> f64 {
+    let [a, b, c] = corners;
+    ((b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y)).abs() / 2.0
+}
+
+#[coast]
+#[derive(Debug, Clone, Copy)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+#[coast]
+pub fn rect_corner(rect: Rect, corner: Corner) -> Point {
+    let Rect { origin, size } = rect;
+    match corner {
+        Corner::TopLeft => origin,
+        Corner::TopRight => Point {
+            x: origin.x + size.x,
+            ..origin
+        },
+        Corner::BottomLeft => Point {
+            y: origin.y + size.y,
+            ..origin
+        },
+        Corner::BottomRight => Point {
+            x: origin.x + size.x,
+            y: origin.y + size.y,
+        },
+    }
+}
+
+#[coast]
+#[derive(Debug, Clone, Copy)]
+pub enum Shape {
+    Empty,
+    Circle { centre: Point, radius: f64 },
+    Rect(Rect),
+}
+
+#[coast]
+pub fn shape_circle(centre: Point, radius: f64) -> Shape {
+    Shape::Circle { centre, radius }
+}
+
+#[coast]
+pub fn shape_area(shape: Shape) -> f64 {
+    match shape {
+        Shape::Empty => 0.0,
+        Shape::Circle { radius, .. } => std::f64::consts::PI * radius * radius,
+        Shape::Rect(rect) => rect_area(rect),
+    }
+}
+
+/// The direction a closed polyline turns in.
+#[coast(invalid = "default")]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Winding {
+    /// The points are in a line, or there aren't enough of them.
+    #[default]
+    None = 0,
+    Clockwise = 1,
+    CounterClockwise = 2,
+}
+
+/// A sequence of connected points.
+#[coast(opaque)]
+#[derive(Debug, Default)]
+pub struct Polyline {
+    points: Vec<Point>,
+}
+
+#[coast]
+impl Polyline {
+    pub fn new() -> Box<Self> {
+        Box::default()
+    }
+
+    pub fn push(&mut self, point: &Point) {
+        self.points.push(*point);
+    }
+
+    pub fn extend(&mut self, points: &[Point]) {
+        self.points.extend_from_slice(points);
+    }
+
+    pub fn insert(&mut self, index: usize, point: &Point) -> Result<(), Error> {
+        if index > self.points.len() {
+            return Err(Error::OutOfRange);
+        }
+        self.points.insert(index, *point);
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Result<Point, Error> {
+        self.points.pop().ok_or(Error::Empty)
+    }
+
+    /// Calls `visit` with each point in order, like looping over [`Self::points`].
+    pub fn for_each(&self, visit: impl FnMut(Point)) {
+        self.points.iter().copied().for_each(visit);
+    }
+
+    pub fn points(&self) -> Vec<Point> {
+        self.points.clone()
+    }
+
+    pub fn last(&self) -> Option<Point> {
+        self.points.last().copied()
+    }
+
+    #[coast(skip)]
+    pub fn as_slice(&self) -> &[Point] {
+        &self.points
+    }
+
+    pub fn length(&self) -> f64 {
+        self.points.windows(2).map(|w| w[0].distance(&w[1])).sum()
+    }
+
+    pub fn winding(&self) -> Winding {
+        let area: f64 = self
+            .points
+            .iter()
+            .zip(self.points.iter().cycle().skip(1))
+            .map(|(a, b)| a.x * b.y - b.x * a.y)
+            .sum();
+        match area.partial_cmp(&0.0) {
+            Some(std::cmp::Ordering::Greater) => Winding::CounterClockwise,
+            Some(std::cmp::Ordering::Less) => Winding::Clockwise,
+            _ => Winding::None,
+        }
+    }
+
+    /// Whether the polyline turns in the given direction.
+    pub fn has_winding(&self, winding: Winding) -> bool {
+        self.winding() == winding
+    }
+
+    /// Consumes the polyline, returning it with its points in the opposite order.
+    pub fn reversed(mut self) -> Self {
+        self.points.reverse();
+        self
+    }
+}
+
+// Only `distance` is exported, since marking a method with `#[coast]` exports only the marked
+// methods, and `origin` stays Rust-only.
+#[coast]
+impl Point {
+    #[coast]
+    pub fn distance(&self, other: &Point) -> f64 {
+        (other.x - self.x).hypot(other.y - self.y)
+    }
+
+    pub fn origin() -> Self {
+        Point { x: 0.0, y: 0.0 }
+    }
+}
+
+coastal::api! {
+    prefix = "geometry";
+    header = "../target/include/geometry.h";
+    last_error = true;
+
+    struct Rect;
+    struct Point;
+    struct Polyline;
+    enum Corner;
+    enum Winding;
+    enum Shape;
+    enum Error;
+    fn rect_new;
+    fn rect_area;
+    fn rect_centre;
+    fn rect_corner;
+    fn triangle_area;
+    fn shape_circle;
+    fn shape_area;
+    impl Polyline;
+    impl Point;
+}
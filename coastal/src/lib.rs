@@ -1,2 +1,11 @@
 pub use coastal_core::*;
 pub use coastal_macros::{api, coast};
+
+/// Converts the error of a `Result` returned to C into a status code.
+///
+/// `#[coast(error)]` implements this for fieldless enums, using their values as the codes. When
+/// the library keeps a last error, the error must also implement `Display` for its message.
+pub trait IntoCError {
+    /// Returns the code for the error, which must not be 0 since that means success.
+    fn error_code(&self) -> std::ffi::c_int;
+}
@@ -0,0 +1,55 @@
+use std::ptr;
+
+use coastal::coast;
+
+#[coast]
+pub const WINDOW: usize = 3;
+
+#[coast]
+pub fn array_sum(values: [i32; 4]) -> i32 {
+    values.iter().sum()
+}
+
+#[coast]
+pub fn array_mean(values: &[f64; WINDOW]) -> f64 {
+    values.iter().sum::<f64>() / WINDOW as f64
+}
+
+#[coast]
+pub fn array_reverse(values: &mut [u8; 4]) {
+    values.reverse();
+}
+
+coastal::api! {
+    prefix = "arr";
+    panic = "error";
+
+    const WINDOW;
+    fn array_sum;
+    fn array_mean;
+    fn array_reverse;
+}
+
+use coastal_wrappers::*;
+
+#[test]
+fn copies_arrays_by_value() {
+    let values = [1, 2, 3, 4];
+    assert_eq!(arr_array_sum(&values), 10);
+}
+
+#[test]
+fn borrows_arrays() {
+    assert_eq!(arr_array_mean(&[1.0, 2.0, 6.0]), 3.0);
+    let mut bytes = *b"abcd";
+    arr_array_reverse(&mut bytes);
+    assert_eq!(&bytes, b"dcba");
+}
+
+#[test]
+fn rejects_null_arrays() {
+    assert_eq!(arr_array_sum(ptr::null()), 0);
+    assert_eq!(arr_array_mean(ptr::null()), 0.0);
+    // Returns as usual, but doesn't write anything.
+    arr_array_reverse(ptr::null_mut());
+}
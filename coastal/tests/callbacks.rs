@@ -0,0 +1,112 @@
+use std::ffi::c_void;
+use std::ptr;
+
+use coastal::coast;
+
+#[coast]
+pub fn callback_apply(value: i32, f: extern "C" fn(i32) -> i32) -> i32 {
+    f(value)
+}
+
+#[coast]
+pub fn callback_maybe_apply(value: i32, f: Option<extern "C" fn(i32) -> i32>) -> i32 {
+    f.map_or(value, |f| f(value))
+}
+
+#[coast]
+pub fn callback_sum(count: u32, f: impl Fn(u32) -> u32) -> u32 {
+    (0..count).map(f).sum()
+}
+
+#[coast]
+pub fn callback_any(count: u32, f: &dyn Fn(u32) -> bool) -> bool {
+    (0..count).any(f)
+}
+
+#[coast]
+pub fn callback_each(count: u32, f: &mut dyn FnMut(u32)) {
+    (0..count).for_each(f);
+}
+
+#[coast]
+pub fn callback_twice(mut f: Box<dyn FnMut()>) {
+    f();
+    f();
+}
+
+coastal::api! {
+    prefix = "cb";
+    panic = "error";
+
+    fn callback_apply;
+    fn callback_maybe_apply;
+    fn callback_sum;
+    fn callback_any;
+    fn callback_each;
+    fn callback_twice;
+}
+
+use coastal_wrappers::*;
+
+extern "C" fn double(value: i32) -> i32 {
+    value * 2
+}
+
+/// Adds the `u32` at `user_data` to `value`.
+unsafe extern "C" fn add_offset(value: u32, user_data: *mut c_void) -> u32 {
+    value + unsafe { *(user_data as *const u32) }
+}
+
+unsafe extern "C" fn is_target(value: u32, user_data: *mut c_void) -> bool {
+    value == unsafe { *(user_data as *const u32) }
+}
+
+/// Appends `value` to the `Vec<u32>` at `user_data`.
+unsafe extern "C" fn push(value: u32, user_data: *mut c_void) {
+    unsafe { (*(user_data as *mut Vec<u32>)).push(value) };
+}
+
+unsafe extern "C" fn count(user_data: *mut c_void) {
+    unsafe { *(user_data as *mut u32) += 1 };
+}
+
+#[test]
+fn calls_function_pointers() {
+    assert_eq!(cb_callback_apply(4, Some(double)), 8);
+    assert_eq!(cb_callback_maybe_apply(4, Some(double)), 8);
+    assert_eq!(cb_callback_maybe_apply(4, None), 4);
+}
+
+#[test]
+fn rejects_null_function_pointers() {
+    assert_eq!(cb_callback_apply(4, None), 0);
+}
+
+#[test]
+fn passes_user_data_to_closures() {
+    let mut offset = 10u32;
+    let user_data = ptr::from_mut(&mut offset).cast();
+    assert_eq!(cb_callback_sum(3, Some(add_offset), user_data), 33);
+    let mut target = 2u32;
+    let user_data = ptr::from_mut(&mut target).cast();
+    assert!(cb_callback_any(3, Some(is_target), user_data));
+    assert!(!cb_callback_any(2, Some(is_target), user_data));
+}
+
+#[test]
+fn calls_mutable_closures() {
+    let mut values: Vec<u32> = Vec::new();
+    cb_callback_each(3, Some(push), ptr::from_mut(&mut values).cast());
+    assert_eq!(values, [0, 1, 2]);
+    let mut calls = 0u32;
+    cb_callback_twice(Some(count), ptr::from_mut(&mut calls).cast());
+    assert_eq!(calls, 2);
+}
+
+#[test]
+fn rejects_null_closures() {
+    assert_eq!(cb_callback_sum(3, None, ptr::null_mut()), 0);
+    assert!(!cb_callback_any(3, None, ptr::null_mut()));
+    // Returns as usual, but doesn't call anything.
+    cb_callback_each(3, None, ptr::null_mut());
+}
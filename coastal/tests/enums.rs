@@ -0,0 +1,116 @@
+use std::process::Command;
+
+use coastal::coast;
+
+#[coast]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Low = 1,
+    High = 5,
+}
+
+#[coast(invalid = "error")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Read,
+    Write,
+}
+
+#[coast(invalid = "default")]
+#[repr(i16)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Fallback {
+    Negative = -1,
+    #[default]
+    Zero = 0,
+    Positive = 1,
+}
+
+#[coast]
+pub fn enum_raise(level: Level) -> Level {
+    match level {
+        Level::Low => Level::High,
+        Level::High => Level::High,
+    }
+}
+
+/// Aborts for invalid levels, even though panics return an error value.
+#[coast(panic = "error")]
+pub fn enum_level_value(level: Level) -> u8 {
+    level as u8
+}
+
+#[coast]
+pub fn enum_toggle(mode: Mode) -> Mode {
+    match mode {
+        Mode::Read => Mode::Write,
+        Mode::Write => Mode::Read,
+    }
+}
+
+#[coast(error_value = -1)]
+pub fn enum_mode_value(mode: Mode) -> i32 {
+    mode as i32
+}
+
+#[coast]
+pub fn enum_fallback_value(fallback: Fallback) -> i16 {
+    fallback as i16
+}
+
+coastal::api! {
+    prefix = "enum";
+
+    enum Level;
+    enum Mode;
+    enum Fallback;
+    fn enum_raise;
+    fn enum_level_value;
+    fn enum_toggle;
+    fn enum_mode_value;
+    fn enum_fallback_value;
+}
+
+use coastal_wrappers::*;
+
+#[test]
+fn converts_valid_values() {
+    assert_eq!(enum_enum_raise(1), 5);
+    assert_eq!(enum_enum_level_value(5), 5);
+    assert_eq!(enum_enum_toggle(0), 1);
+    assert_eq!(enum_enum_mode_value(1), 1);
+    assert_eq!(enum_enum_fallback_value(-1), -1);
+}
+
+#[test]
+fn returns_error_value_for_invalid_values() {
+    assert_eq!(enum_enum_toggle(2), 0);
+    assert_eq!(enum_enum_mode_value(-3), -1);
+}
+
+#[test]
+fn uses_default_for_invalid_values() {
+    assert_eq!(enum_enum_fallback_value(7), 0);
+    assert_eq!(enum_enum_fallback_value(i16::MIN), 0);
+}
+
+#[test]
+fn aborts_on_invalid_values() {
+    // The abort is checked in a copy of this test process.
+    if std::env::var_os("COASTAL_ENUM_ABORT").is_some() {
+        enum_enum_level_value(2);
+        return;
+    }
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["aborts_on_invalid_values", "--exact", "--nocapture"])
+        .env("COASTAL_ENUM_ABORT", "1")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Coastal: argument 'level' has invalid value 2 for 'Level', aborting"),
+        "{stderr}"
+    );
+}
@@ -0,0 +1,79 @@
+use coastal::coast;
+
+#[coast]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Celsius {
+    pub degrees: f64,
+}
+
+impl PartialOrd for Celsius {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.degrees.partial_cmp(&other.degrees)
+    }
+}
+
+#[coast(
+    instantiate(T = u8, suffix = "_u8"),
+    instantiate(T = Celsius, suffix = "_celsius")
+)]
+pub fn generic_max<T: PartialOrd + Copy>(a: T, b: T) -> T {
+    if b > a {
+        b
+    } else {
+        a
+    }
+}
+
+#[coast(instantiate(T = i16, U = i64, suffix = "_i16_i64"))]
+pub fn generic_widen<T: Copy, U: From<T>>(values: &[T]) -> Vec<U> {
+    values.iter().map(|&v| U::from(v)).collect()
+}
+
+#[coast(name = "generic_renamed", instantiate(T = u32, suffix = "_u32"))]
+pub fn generic_first<T: Copy>(values: &[T]) -> Option<T> {
+    values.first().copied()
+}
+
+coastal::api! {
+    prefix = "gen";
+
+    struct Celsius;
+    fn generic_max;
+    fn generic_widen;
+    fn generic_first;
+}
+
+use coastal_wrappers::*;
+
+#[test]
+fn exports_each_instantiation() {
+    assert_eq!(gen_generic_max_u8(3, 9), 9);
+    let warm = Celsius { degrees: 21.5 };
+    let cold = Celsius { degrees: -4.0 };
+    assert_eq!(gen_generic_max_celsius(warm, cold), warm);
+}
+
+#[test]
+fn substitutes_every_parameter() {
+    let values = [-1i16, 300];
+    let wide = gen_generic_widen_i16_i64(values.as_ptr(), values.len());
+    assert_eq!(
+        unsafe { std::slice::from_raw_parts(wide.ptr, wide.len) },
+        [-1i64, 300]
+    );
+    gen_i64_slice_free(wide);
+}
+
+#[test]
+fn adds_suffix_to_name() {
+    let values = [7u32, 8];
+    let mut first = 0;
+    assert!(generic_renamed_u32(
+        values.as_ptr(),
+        values.len(),
+        &mut first
+    ));
+    assert_eq!(first, 7);
+    assert!(!generic_renamed_u32(std::ptr::null(), 0, &mut first));
+}
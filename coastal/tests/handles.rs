@@ -0,0 +1,112 @@
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use coastal::coast;
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+#[coast(opaque)]
+pub struct Ledger {
+    entries: Vec<i64>,
+}
+
+/// Named after a keyword, which the free function's argument would be too.
+#[coast(opaque)]
+pub struct Match {
+    pub score: u32,
+}
+
+impl Drop for Ledger {
+    fn drop(&mut self) {
+        DROPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[coast]
+pub fn handle_new() -> Box<Ledger> {
+    Box::new(Ledger {
+        entries: Vec::new(),
+    })
+}
+
+#[coast]
+pub fn handle_record(ledger: &mut Ledger, amount: i64) {
+    ledger.entries.push(amount);
+}
+
+#[coast]
+pub fn handle_balance(ledger: &Ledger) -> i64 {
+    ledger.entries.iter().sum()
+}
+
+/// Consumes the ledger, returning how many entries it had.
+#[coast]
+pub fn handle_close(ledger: Ledger) -> usize {
+    ledger.entries.len()
+}
+
+/// Consumes `from`, moving its entries to `into`.
+#[coast]
+#[allow(clippy::boxed_local)]
+pub fn handle_merge(into: &mut Ledger, mut from: Box<Ledger>) {
+    into.entries.append(&mut from.entries);
+}
+
+/// Returns a new ledger by value, which C owns through a pointer.
+#[coast]
+pub fn handle_copy(ledger: &Ledger) -> Ledger {
+    Ledger {
+        entries: ledger.entries.clone(),
+    }
+}
+
+coastal::api! {
+    prefix = "handle";
+    panic = "error";
+
+    struct Ledger;
+    struct Match;
+    fn handle_new;
+    fn handle_record;
+    fn handle_balance;
+    fn handle_close;
+    fn handle_merge;
+    fn handle_copy;
+}
+
+use coastal_wrappers::*;
+
+#[test]
+fn uses_handles() {
+    let ledger = handle_handle_new();
+    handle_handle_record(ledger, 5);
+    handle_handle_record(ledger, -2);
+    assert_eq!(handle_handle_balance(ledger), 3);
+    let copy = handle_handle_copy(ledger);
+    handle_handle_record(copy, 1);
+    assert_eq!(handle_handle_balance(copy), 4);
+    assert_eq!(handle_handle_balance(ledger), 3);
+    let dropped = DROPPED.load(Ordering::SeqCst);
+    handle_handle_merge(ledger, copy);
+    assert_eq!(handle_handle_balance(ledger), 7);
+    let other = handle_handle_new();
+    handle_ledger_free(other);
+    assert_eq!(handle_handle_close(ledger), 5);
+    assert_eq!(DROPPED.load(Ordering::SeqCst), dropped + 3);
+}
+
+#[test]
+fn frees_null_handles() {
+    handle_ledger_free(ptr::null_mut());
+    handle_match_free(ptr::null_mut());
+}
+
+#[test]
+fn rejects_null_handles() {
+    assert_eq!(handle_handle_balance(ptr::null()), 0);
+    assert_eq!(handle_handle_close(ptr::null_mut()), 0);
+    handle_handle_record(ptr::null_mut(), 1);
+    let ledger = handle_handle_new();
+    handle_handle_merge(ledger, ptr::null_mut());
+    handle_ledger_free(ledger);
+}
@@ -0,0 +1,133 @@
+use coastal::coast;
+
+#[coast(name = "ITEM_LIMIT", since = "1.1")]
+pub const LIMIT: u32 = 10;
+
+#[coast(skip)]
+pub const HIDDEN_LIMIT: u32 = 20;
+
+/// Adds two numbers.
+#[coast(name = "item_plus", since = "1.2")]
+pub fn item_add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[coast(deprecated = "use item_plus() instead")]
+pub fn item_sum(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[coast(doc = "Returns the answer.")]
+pub fn item_answer() -> u8 {
+    42
+}
+
+#[coast(skip)]
+pub fn item_hidden() -> u8 {
+    0
+}
+
+#[coast(unsafe, c_type = "const void*")]
+pub fn item_first(#[coast(c_type = "const void*")] data: *const u8) -> *const u8 {
+    data
+}
+
+#[coast(panic = "error")]
+pub fn item_checked(value: u32) -> u32 {
+    value.checked_mul(2).expect("value is too large")
+}
+
+#[coast(opaque)]
+#[derive(Default)]
+pub struct Register {
+    value: u32,
+}
+
+#[coast]
+impl Register {
+    #[coast(export)]
+    pub fn new() -> Box<Self> {
+        Box::default()
+    }
+
+    #[coast(export, name = "item_register_peek")]
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    pub fn set(&mut self, value: u32) {
+        self.value = value;
+    }
+}
+
+coastal::api! {
+    prefix = "item";
+    header = "../target/include/item_options.h";
+
+    const LIMIT;
+    const HIDDEN_LIMIT;
+    struct Register;
+    fn item_add;
+    fn item_sum;
+    fn item_answer;
+    fn item_hidden;
+    fn item_first;
+    fn item_checked;
+    impl Register;
+}
+
+use coastal_wrappers::*;
+
+fn header() -> String {
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../target/include/item_options.h"
+    );
+    std::fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn renames_items() {
+    assert_eq!(item_plus(2, 3), 5);
+    assert_eq!(item_register_peek(&Register { value: 4 }), 4);
+    let header = header();
+    assert!(header.contains("#define ITEM_LIMIT UINT32_C(10)"));
+    assert!(header.contains("int32_t item_plus(int32_t a, int32_t b);"));
+    assert!(header.contains("uint32_t item_register_peek(const ItemRegister* register_);"));
+}
+
+#[test]
+fn skips_items() {
+    let header = header();
+    assert!(!header.contains("HIDDEN_LIMIT"));
+    assert!(!header.contains("item_hidden"));
+    // Only the exported methods are in the library, and `set` is only for Rust.
+    assert!(header.contains("item_register_new"));
+    assert!(!header.contains("item_register_set"));
+    let mut register = Register::new();
+    register.set(1);
+}
+
+#[test]
+fn documents_items() {
+    assert_eq!(item_item_answer(), 42);
+    assert_eq!(item_item_sum(1, 2), 3);
+    let header = header();
+    assert!(header.contains("@since 1.1"));
+    assert!(header.contains(" * Adds two numbers.\n *\n * @since 1.2\n"));
+    assert!(header.contains("/** Returns the answer. */"));
+    assert!(header.contains("ITEM_DEPRECATED(\"use item_plus() instead\") int32_t item_item_sum("));
+}
+
+#[test]
+fn overrides_c_types() {
+    let data = [1u8, 2];
+    assert_eq!(item_item_first(data.as_ptr()), data.as_ptr());
+    assert!(header().contains("const void* item_item_first(const void* data);"));
+}
+
+#[test]
+fn overrides_panic_policy() {
+    assert_eq!(item_item_checked(2), 4);
+    assert_eq!(item_item_checked(u32::MAX), 0);
+}
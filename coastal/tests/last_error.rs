@@ -0,0 +1,73 @@
+use std::ffi::CStr;
+use std::thread;
+
+use coastal::coast;
+
+#[coast(error)]
+#[derive(Debug, Clone, Copy)]
+pub enum Fault {
+    Empty = 1,
+    TooLong,
+}
+
+impl std::fmt::Display for Fault {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            Fault::Empty => "name is empty",
+            Fault::TooLong => "name is too long",
+        })
+    }
+}
+
+#[coast]
+pub fn last_check(len: usize) -> Result<(), Fault> {
+    match len {
+        0 => Err(Fault::Empty),
+        1..=8 => Ok(()),
+        _ => Err(Fault::TooLong),
+    }
+}
+
+coastal::api! {
+    prefix = "le";
+    last_error = true;
+
+    enum Fault;
+    fn last_check;
+}
+
+use coastal_wrappers::*;
+
+fn last_message() -> Option<&'static CStr> {
+    let message = le_last_error_message();
+    (!message.is_null()).then(|| unsafe { CStr::from_ptr(message) })
+}
+
+#[test]
+fn keeps_the_last_error() {
+    le_clear_error();
+    assert_eq!(le_last_error_code(), 0);
+    assert_eq!(last_message(), None);
+    assert_eq!(le_last_check(0), 1);
+    assert_eq!(le_last_error_code(), 1);
+    assert_eq!(last_message(), Some(c"name is empty"));
+    // Success doesn't clear the error, which is replaced by the next one.
+    assert_eq!(le_last_check(4), 0);
+    assert_eq!(le_last_error_code(), 1);
+    assert_eq!(le_last_check(9), 2);
+    assert_eq!(le_last_error_code(), 2);
+    assert_eq!(last_message(), Some(c"name is too long"));
+    le_clear_error();
+    assert_eq!(le_last_error_code(), 0);
+    assert_eq!(last_message(), None);
+}
+
+#[test]
+fn keeps_errors_per_thread() {
+    le_clear_error();
+    thread::spawn(|| assert_eq!(le_last_check(0), 1))
+        .join()
+        .unwrap();
+    assert_eq!(le_last_error_code(), 0);
+    assert_eq!(last_message(), None);
+}
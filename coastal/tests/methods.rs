@@ -0,0 +1,145 @@
+use std::ptr;
+
+use coastal::coast;
+
+#[coast]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pair {
+    pub first: i32,
+    pub second: i32,
+}
+
+#[coast(opaque)]
+#[derive(Default)]
+pub struct Stack {
+    values: Vec<i32>,
+}
+
+/// Named after a keyword, which the receivers of its methods would be too.
+#[coast(opaque)]
+#[derive(Default)]
+pub struct Loop {
+    turns: u32,
+}
+
+#[coast]
+impl Loop {
+    pub fn new() -> Box<Self> {
+        Box::default()
+    }
+
+    pub fn turn(&mut self) -> u32 {
+        self.turns += 1;
+        self.turns
+    }
+}
+
+#[coast]
+impl Pair {
+    pub fn new(first: i32, second: i32) -> Self {
+        Pair { first, second }
+    }
+
+    pub fn sum(self) -> i32 {
+        self.first + self.second
+    }
+
+    pub fn max(&self) -> i32 {
+        self.first.max(self.second)
+    }
+
+    pub fn swap(&mut self) {
+        std::mem::swap(&mut self.first, &mut self.second);
+    }
+}
+
+// Only the marked methods are exported.
+#[coast]
+impl Stack {
+    #[coast]
+    pub fn new() -> Self {
+        Stack::default()
+    }
+
+    #[coast]
+    pub fn push(&mut self, value: i32) {
+        self.values.push(value);
+    }
+
+    #[coast]
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    #[coast]
+    pub fn with_pair(pair: Pair) -> Box<Self> {
+        Box::new(Stack {
+            values: vec![pair.first, pair.second],
+        })
+    }
+
+    /// Consumes the stack.
+    #[coast]
+    pub fn into_total(self) -> i64 {
+        self.values.iter().map(|&v| i64::from(v)).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+coastal::api! {
+    prefix = "meth";
+    panic = "error";
+
+    struct Pair;
+    struct Stack;
+    struct Loop;
+    impl Pair;
+    impl Stack;
+    impl Loop;
+}
+
+use coastal_wrappers::*;
+
+#[test]
+fn calls_value_methods() {
+    let mut pair = meth_pair_new(3, 8);
+    assert_eq!(pair, Pair::new(3, 8));
+    assert_eq!(meth_pair_sum(pair), 11);
+    assert_eq!(meth_pair_max(&pair), 8);
+    meth_pair_swap(&mut pair);
+    assert_eq!(pair, Pair::new(8, 3));
+}
+
+#[test]
+fn calls_handle_methods() {
+    // `is_empty` isn't marked, so it's only for Rust.
+    assert!(Stack::new().is_empty());
+    let stack = meth_stack_new();
+    meth_stack_push(stack, 4);
+    meth_stack_push(stack, 5);
+    assert_eq!(meth_stack_len(stack), 2);
+    assert_eq!(meth_stack_into_total(stack), 9);
+    let stack = meth_stack_with_pair(Pair::new(1, 2));
+    assert_eq!(meth_stack_len(stack), 2);
+    meth_stack_free(stack);
+}
+
+#[test]
+fn names_receivers_after_keywords() {
+    let turns = meth_loop_new();
+    assert_eq!(meth_loop_turn(turns), 1);
+    assert_eq!(meth_loop_turn(turns), 2);
+    meth_loop_free(turns);
+}
+
+#[test]
+fn rejects_null_receivers() {
+    assert_eq!(meth_pair_max(ptr::null()), 0);
+    meth_pair_swap(ptr::null_mut());
+    assert_eq!(meth_stack_len(ptr::null()), 0);
+    assert_eq!(meth_stack_into_total(ptr::null_mut()), 0);
+}
@@ -0,0 +1,181 @@
+use std::num::NonZeroU32;
+use std::ptr::{self, NonNull};
+
+use coastal::coast;
+
+#[coast]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub lo: f64,
+    pub hi: f64,
+}
+
+#[coast(opaque)]
+#[derive(Default)]
+pub struct Bag {
+    items: Vec<u32>,
+}
+
+#[coast]
+pub fn opt_width(interval: Option<&Interval>) -> f64 {
+    interval.map_or(0.0, |i| i.hi - i.lo)
+}
+
+#[coast]
+pub fn opt_widen(interval: Option<&mut Interval>) {
+    if let Some(interval) = interval {
+        interval.hi += 1.0;
+    }
+}
+
+#[coast]
+pub fn opt_bag(items: Option<u32>) -> Option<Box<Bag>> {
+    items.map(|n| {
+        Box::new(Bag {
+            items: (0..n).collect(),
+        })
+    })
+}
+
+#[coast]
+pub fn opt_bag_len(bag: Option<Box<Bag>>) -> usize {
+    bag.map_or(0, |bag| bag.items.len())
+}
+
+#[coast]
+pub fn opt_first(bag: &Bag) -> Option<&u32> {
+    bag.items.first()
+}
+
+#[coast]
+pub fn opt_last_mut(bag: &mut Bag) -> Option<&mut u32> {
+    bag.items.last_mut()
+}
+
+#[coast(unsafe)]
+pub fn opt_non_null(value: Option<NonNull<u32>>) -> Option<NonNull<u32>> {
+    value
+}
+
+#[coast]
+pub fn opt_halve(value: Option<NonZeroU32>) -> Option<NonZeroU32> {
+    NonZeroU32::new(value?.get() / 2)
+}
+
+#[coast]
+pub fn opt_apply(value: u32, f: Option<extern "C" fn(u32) -> u32>) -> u32 {
+    f.map_or(value, |f| f(value))
+}
+
+#[coast]
+pub fn opt_or(value: Option<i32>, #[coast(nullable)] fallback: Option<i32>) -> i32 {
+    value.or(fallback).unwrap_or(-1)
+}
+
+#[coast]
+pub fn opt_sqrt(value: f64) -> Option<f64> {
+    (value >= 0.0).then(|| value.sqrt())
+}
+
+/// Has an argument with the same name as the out-parameter.
+#[coast]
+pub fn opt_checked_inc(out: u32) -> Option<u32> {
+    out.checked_add(1)
+}
+
+coastal::api! {
+    prefix = "opt";
+
+    struct Interval;
+    struct Bag;
+    fn opt_width;
+    fn opt_widen;
+    fn opt_bag;
+    fn opt_bag_len;
+    fn opt_first;
+    fn opt_last_mut;
+    fn opt_non_null;
+    fn opt_halve;
+    fn opt_apply;
+    fn opt_or;
+    fn opt_sqrt;
+    fn opt_checked_inc;
+}
+
+use coastal_wrappers::*;
+
+#[test]
+fn references_are_nullable() {
+    let mut interval = Interval { lo: 1.0, hi: 4.0 };
+    assert_eq!(opt_opt_width(&interval), 3.0);
+    assert_eq!(opt_opt_width(ptr::null()), 0.0);
+    opt_opt_widen(&mut interval);
+    opt_opt_widen(ptr::null_mut());
+    assert_eq!(interval.hi, 5.0);
+}
+
+#[test]
+fn boxes_are_nullable() {
+    assert!(opt_opt_bag(false, 0).is_null());
+    let bag = opt_opt_bag(true, 3);
+    assert!(!bag.is_null());
+    assert_eq!(unsafe { *opt_opt_first(bag) }, 0);
+    unsafe { *opt_opt_last_mut(bag) = 7 };
+    assert_eq!(unsafe { (&*bag).items[2] }, 7);
+    // Passing the box consumes it.
+    assert_eq!(opt_opt_bag_len(bag), 3);
+    assert_eq!(opt_opt_bag_len(ptr::null_mut()), 0);
+    let empty = opt_opt_bag(true, 0);
+    assert!(opt_opt_first(empty).is_null());
+    assert!(opt_opt_last_mut(empty).is_null());
+    opt_bag_free(empty);
+}
+
+#[test]
+fn non_null_passes_through() {
+    let mut value = 1;
+    let pointer = NonNull::from(&mut value);
+    assert_eq!(opt_opt_non_null(Some(pointer)), Some(pointer));
+    assert_eq!(opt_opt_non_null(None), None);
+}
+
+#[test]
+fn non_zero_integers_are_zero_for_none() {
+    assert_eq!(opt_opt_halve(10), 5);
+    assert_eq!(opt_opt_halve(1), 0);
+    assert_eq!(opt_opt_halve(0), 0);
+}
+
+#[test]
+fn function_pointers_are_nullable() {
+    extern "C" fn square(value: u32) -> u32 {
+        value * value
+    }
+    assert_eq!(opt_opt_apply(3, Some(square)), 9);
+    assert_eq!(opt_opt_apply(3, None), 3);
+}
+
+#[test]
+fn values_are_flagged_or_nullable() {
+    assert_eq!(opt_opt_or(true, 1, &2), 1);
+    assert_eq!(opt_opt_or(false, 1, &2), 2);
+    assert_eq!(opt_opt_or(false, 1, ptr::null()), -1);
+}
+
+#[test]
+fn values_are_returned_through_out() {
+    let mut out = 0.0;
+    assert!(opt_opt_sqrt(9.0, &mut out));
+    assert_eq!(out, 3.0);
+    assert!(!opt_opt_sqrt(-1.0, &mut out));
+    assert!(opt_opt_sqrt(4.0, ptr::null_mut()));
+}
+
+#[test]
+fn renames_out_parameter_after_arguments() {
+    let mut result = 0;
+    assert!(opt_opt_checked_inc(1, &mut result));
+    assert_eq!(result, 2);
+    assert!(!opt_opt_checked_inc(u32::MAX, &mut result));
+}
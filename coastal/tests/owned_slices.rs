@@ -0,0 +1,87 @@
+use coastal::coast;
+
+#[coast]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Knot {
+    pub position: f32,
+    pub weight: f32,
+}
+
+#[coast]
+pub fn owned_range(len: u32) -> Vec<u32> {
+    // Extra capacity must still be freed.
+    let mut values = Vec::with_capacity(len as usize * 2);
+    values.extend(0..len);
+    values
+}
+
+#[coast]
+pub fn owned_halves(len: u32) -> Box<[f32]> {
+    (0..len).map(|n| n as f32 / 2.0).collect()
+}
+
+#[coast]
+pub fn owned_knots(len: u32) -> Vec<Knot> {
+    (0..len)
+        .map(|n| Knot {
+            position: n as f32,
+            weight: 1.0,
+        })
+        .collect()
+}
+
+coastal::api! {
+    prefix = "owned";
+
+    struct Knot;
+    fn owned_range;
+    fn owned_halves;
+    fn owned_knots;
+}
+
+use coastal_wrappers::*;
+
+#[test]
+fn returns_vectors() {
+    let range = owned_owned_range(4);
+    assert_eq!(range.len, 4);
+    assert_eq!(
+        unsafe { std::slice::from_raw_parts(range.ptr, range.len) },
+        [0, 1, 2, 3]
+    );
+    owned_u32_slice_free(range);
+}
+
+#[test]
+fn returns_boxed_slices() {
+    let halves = owned_owned_halves(3);
+    assert_eq!(
+        unsafe { std::slice::from_raw_parts(halves.ptr, halves.len) },
+        [0.0, 0.5, 1.0]
+    );
+    owned_f32_slice_free(halves);
+}
+
+#[test]
+fn returns_structs() {
+    let knots = owned_owned_knots(2);
+    let slice = unsafe { std::slice::from_raw_parts(knots.ptr, knots.len) };
+    assert_eq!(
+        slice[1],
+        Knot {
+            position: 1.0,
+            weight: 1.0
+        }
+    );
+    owned_knot_slice_free(knots);
+}
+
+#[test]
+fn empty_slices_are_null() {
+    let range = owned_owned_range(0);
+    assert!(range.ptr.is_null());
+    assert_eq!(range.len, 0);
+    owned_u32_slice_free(range);
+    owned_f32_slice_free(owned_owned_halves(0));
+}
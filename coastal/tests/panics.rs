@@ -0,0 +1,153 @@
+use std::ffi::CStr;
+use std::process::Command;
+
+use coastal::coast;
+
+#[coast]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reading {
+    pub value: f64,
+    pub valid: bool,
+}
+
+#[coast(opaque)]
+pub struct Gauge {
+    pub reading: Reading,
+}
+
+#[coast]
+pub fn panic_int() -> i32 {
+    panic!("no int")
+}
+
+#[coast]
+pub fn panic_flag() -> bool {
+    panic!("no flag")
+}
+
+#[coast]
+pub fn panic_float() -> f64 {
+    panic!("no float")
+}
+
+#[coast(error_value = -1)]
+pub fn panic_custom() -> i64 {
+    panic!("no custom value")
+}
+
+#[coast]
+pub fn panic_string() -> String {
+    panic!("no string")
+}
+
+#[coast]
+pub fn panic_reading() -> Reading {
+    panic!("no reading")
+}
+
+#[coast]
+pub fn panic_gauge() -> Box<Gauge> {
+    panic!("no gauge")
+}
+
+#[coast]
+pub fn panic_bytes() -> Vec<u8> {
+    panic!("no bytes")
+}
+
+#[coast]
+pub fn panic_void(fail: bool) {
+    assert!(!fail, "no void");
+}
+
+#[coast(panic = "abort")]
+pub fn panic_abort() -> i32 {
+    panic!("no abort")
+}
+
+#[coast(panic = "last_error")]
+pub fn panic_kept(value: u32) -> u32 {
+    value.checked_sub(1).expect("value must be positive")
+}
+
+coastal::api! {
+    prefix = "panic";
+    panic = "error";
+
+    struct Reading;
+    struct Gauge;
+    fn panic_int;
+    fn panic_flag;
+    fn panic_float;
+    fn panic_custom;
+    fn panic_string;
+    fn panic_reading;
+    fn panic_gauge;
+    fn panic_bytes;
+    fn panic_void;
+    fn panic_abort;
+    fn panic_kept;
+}
+
+use coastal_wrappers::*;
+
+#[test]
+fn returns_zero_values() {
+    assert_eq!(panic_panic_int(), 0);
+    assert!(!panic_panic_flag());
+    assert_eq!(panic_panic_float(), 0.0);
+    assert_eq!(
+        panic_panic_reading(),
+        Reading {
+            value: 0.0,
+            valid: false
+        }
+    );
+    panic_panic_void(true);
+}
+
+#[test]
+fn returns_null_pointers() {
+    assert!(panic_panic_string().is_null());
+    assert!(panic_panic_gauge().is_null());
+    let bytes = panic_panic_bytes();
+    assert!(bytes.ptr.is_null());
+    assert_eq!(bytes.len, 0);
+}
+
+#[test]
+fn returns_custom_error_value() {
+    assert_eq!(panic_panic_custom(), -1);
+}
+
+#[test]
+fn keeps_panic_message() {
+    panic_clear_error();
+    assert_eq!(panic_panic_kept(2), 1);
+    assert_eq!(panic_last_error_code(), 0);
+    assert_eq!(panic_panic_kept(0), 0);
+    assert_eq!(panic_last_error_code(), -1);
+    let message = unsafe { CStr::from_ptr(panic_last_error_message()) };
+    assert_eq!(message, c"value must be positive");
+}
+
+#[test]
+fn aborts_on_panic() {
+    // The abort is checked in a copy of this test process.
+    if std::env::var_os("COASTAL_PANIC_ABORT").is_some() {
+        panic_panic_abort();
+        return;
+    }
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["aborts_on_panic", "--exact", "--nocapture"])
+        .env("COASTAL_PANIC_ABORT", "1")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Coastal: 'panic_panic_abort' panicked, aborting"),
+        "{stderr}"
+    );
+}
@@ -0,0 +1,105 @@
+use std::ptr;
+
+use coastal::coast;
+
+#[coast]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerKind {
+    Null,
+    NonNull,
+}
+
+/// # Safety
+///
+/// Any pointer is allowed, since it isn't dereferenced.
+#[coast]
+pub unsafe fn pointer_kind(value: *const u32) -> PointerKind {
+    if value.is_null() {
+        PointerKind::Null
+    } else {
+        PointerKind::NonNull
+    }
+}
+
+/// # Safety
+///
+/// `value` must be valid for reads.
+#[coast]
+pub unsafe fn pointer_read(value: *const u32) -> u32 {
+    unsafe { *value }
+}
+
+/// # Safety
+///
+/// `a` and `b` must be valid for reads and writes.
+#[coast]
+pub unsafe fn pointer_swap(a: *mut u32, b: *mut u32) {
+    unsafe { ptr::swap(a, b) };
+}
+
+#[coast(unsafe)]
+pub fn pointer_offset(base: *const u8, offset: usize) -> *const u8 {
+    base.wrapping_add(offset)
+}
+
+#[coast(opaque)]
+#[derive(Default)]
+pub struct Tally {
+    count: u32,
+}
+
+#[coast]
+impl Tally {
+    pub fn new() -> Box<Self> {
+        Box::default()
+    }
+
+    pub fn count(&self) -> &u32 {
+        &self.count
+    }
+
+    pub fn count_mut(&mut self) -> &mut u32 {
+        &mut self.count
+    }
+}
+
+coastal::api! {
+    prefix = "ptr";
+
+    struct Tally;
+    enum PointerKind;
+    fn pointer_kind;
+    fn pointer_read;
+    fn pointer_swap;
+    fn pointer_offset;
+    impl Tally;
+}
+
+use coastal_wrappers::*;
+
+#[test]
+fn unsafe_fn_returns_enum() {
+    assert_eq!(ptr_pointer_kind(ptr::null()), 0);
+    assert_eq!(ptr_pointer_kind(&1), 1);
+}
+
+#[test]
+fn passes_pointers_through() {
+    assert_eq!(ptr_pointer_read(&7), 7);
+    let (mut a, mut b) = (1, 2);
+    ptr_pointer_swap(&mut a, &mut b);
+    assert_eq!((a, b), (2, 1));
+    let bytes = b"abc";
+    assert_eq!(
+        ptr_pointer_offset(bytes.as_ptr(), 2),
+        &bytes[2] as *const u8
+    );
+}
+
+#[test]
+fn returns_references_as_pointers() {
+    let tally = ptr_tally_new();
+    unsafe { *ptr_tally_count_mut(tally) = 3 };
+    assert_eq!(unsafe { *ptr_tally_count(tally) }, 3);
+    ptr_tally_free(tally);
+}
@@ -0,0 +1,108 @@
+use std::ffi::{c_char, c_int, CStr};
+use std::ptr;
+
+use coastal::{coast, IntoCError};
+
+#[coast(error)]
+#[derive(Debug, Clone, Copy)]
+pub enum ParseError {
+    Empty = 1,
+    Invalid,
+}
+
+/// An error with codes that don't come from an enum.
+#[derive(Debug)]
+pub struct Overflow(c_int);
+
+impl IntoCError for Overflow {
+    fn error_code(&self) -> c_int {
+        self.0
+    }
+}
+
+#[coast]
+pub fn parse_digit(text: &str) -> Result<u8, ParseError> {
+    match text.as_bytes() {
+        [] => Err(ParseError::Empty),
+        [digit @ b'0'..=b'9'] => Ok(digit - b'0'),
+        _ => Err(ParseError::Invalid),
+    }
+}
+
+#[coast]
+pub fn parse_check(text: &str) -> Result<(), ParseError> {
+    parse_digit(text).map(drop)
+}
+
+#[coast]
+pub fn parse_double(value: u32) -> Result<u32, Overflow> {
+    value.checked_mul(2).ok_or(Overflow(42))
+}
+
+#[coast]
+pub fn parse_echo(text: &str) -> Result<String, ParseError> {
+    if text.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    Ok(text.to_owned())
+}
+
+/// Has an argument with the same name as the out-parameter.
+#[coast]
+pub fn parse_negate(out: i32) -> Result<i32, Overflow> {
+    out.checked_neg().ok_or(Overflow(7))
+}
+
+coastal::api! {
+    prefix = "parse";
+
+    enum ParseError;
+    fn parse_digit;
+    fn parse_check;
+    fn parse_double;
+    fn parse_echo;
+    fn parse_negate;
+}
+
+use coastal_wrappers::*;
+
+fn c_str(text: &CStr) -> *const c_char {
+    text.as_ptr()
+}
+
+#[test]
+fn writes_ok_value_to_out() {
+    let mut out = 0;
+    assert_eq!(parse_parse_digit(c_str(c"7"), &mut out), 0);
+    assert_eq!(out, 7);
+    // A null out-parameter only discards the value.
+    assert_eq!(parse_parse_digit(c_str(c"7"), ptr::null_mut()), 0);
+}
+
+#[test]
+fn returns_error_codes() {
+    let mut out = 99;
+    assert_eq!(parse_parse_digit(c_str(c""), &mut out), 1);
+    assert_eq!(parse_parse_digit(c_str(c"x"), &mut out), 2);
+    assert_eq!(out, 99);
+    assert_eq!(parse_parse_check(c_str(c"3")), 0);
+    assert_eq!(parse_parse_check(c_str(c"33")), 2);
+    assert_eq!(parse_parse_double(u32::MAX, &mut 0), 42);
+}
+
+#[test]
+fn writes_owned_strings_to_out() {
+    let mut out = ptr::null_mut();
+    assert_eq!(parse_parse_echo(c_str(c"hi"), &mut out), 0);
+    assert_eq!(unsafe { CStr::from_ptr(out) }, c"hi");
+    parse_string_free(out);
+    assert_eq!(parse_parse_echo(c_str(c""), &mut out), 1);
+}
+
+#[test]
+fn renames_out_parameter_after_arguments() {
+    let mut result = 0;
+    assert_eq!(parse_parse_negate(5, &mut result), 0);
+    assert_eq!(result, -5);
+    assert_eq!(parse_parse_negate(i32::MIN, &mut result), 7);
+}
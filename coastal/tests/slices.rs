@@ -0,0 +1,81 @@
+use std::ptr;
+
+use coastal::coast;
+
+#[coast]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub time: f64,
+    pub value: f64,
+}
+
+#[coast]
+pub fn slice_sum(values: &[i64]) -> i64 {
+    values.iter().sum()
+}
+
+#[coast]
+pub fn slice_latest(samples: &[Sample]) -> f64 {
+    samples
+        .iter()
+        .max_by(|a, b| a.time.total_cmp(&b.time))
+        .map_or(f64::NAN, |s| s.value)
+}
+
+#[coast]
+pub fn slice_double(values: &mut [u16]) -> usize {
+    values.iter_mut().for_each(|v| *v *= 2);
+    values.len()
+}
+
+coastal::api! {
+    prefix = "sl";
+    panic = "error";
+
+    struct Sample;
+    fn slice_sum;
+    fn slice_latest;
+    fn slice_double;
+}
+
+use coastal_wrappers::*;
+
+#[test]
+fn converts_slices() {
+    let values = [1, 2, 3, -10];
+    assert_eq!(sl_slice_sum(values.as_ptr(), 3), 6);
+    assert_eq!(sl_slice_sum(values.as_ptr(), values.len()), -4);
+    let samples = [
+        Sample {
+            time: 2.0,
+            value: 5.0,
+        },
+        Sample {
+            time: 1.0,
+            value: 7.0,
+        },
+    ];
+    assert_eq!(sl_slice_latest(samples.as_ptr(), samples.len()), 5.0);
+}
+
+#[test]
+fn converts_mutable_slices() {
+    let mut values = [1, 2, 3];
+    assert_eq!(sl_slice_double(values.as_mut_ptr(), 2), 2);
+    assert_eq!(values, [2, 4, 3]);
+}
+
+#[test]
+fn null_with_zero_length_is_empty() {
+    assert_eq!(sl_slice_sum(ptr::null(), 0), 0);
+    assert!(sl_slice_latest(ptr::null(), 0).is_nan());
+    assert_eq!(sl_slice_double(ptr::null_mut(), 0), 0);
+}
+
+#[test]
+fn rejects_null_with_length() {
+    // The error value for `f64` is zero, unlike the empty result.
+    assert_eq!(sl_slice_latest(ptr::null(), 1), 0.0);
+    assert_eq!(sl_slice_double(ptr::null_mut(), 3), 0);
+}
@@ -0,0 +1,85 @@
+use std::ffi::{CStr, CString};
+
+use coastal::coast;
+
+#[coast]
+pub fn returned_greeting(loud: bool) -> String {
+    if loud {
+        "HELLO".to_owned()
+    } else {
+        "hello".to_owned()
+    }
+}
+
+#[coast]
+pub fn returned_maybe(found: bool) -> Option<String> {
+    found.then(|| "found".to_owned())
+}
+
+#[coast]
+pub fn returned_c_string() -> CString {
+    c"owned".to_owned()
+}
+
+#[coast]
+pub fn returned_name(index: u32) -> &'static str {
+    ["zero", "one"][index as usize % 2]
+}
+
+#[coast]
+pub fn returned_c_name() -> &'static CStr {
+    c"static"
+}
+
+#[coast]
+pub fn returned_nul() -> String {
+    "before\0after".to_owned()
+}
+
+coastal::api! {
+    prefix = "ret";
+    interior_nul = "error";
+
+    fn returned_greeting;
+    fn returned_maybe;
+    fn returned_c_string;
+    fn returned_name;
+    fn returned_c_name;
+    fn returned_nul;
+}
+
+use coastal_wrappers::*;
+
+#[test]
+fn returns_owned_strings() {
+    let greeting = ret_returned_greeting(true);
+    assert_eq!(unsafe { CStr::from_ptr(greeting) }, c"HELLO");
+    ret_string_free(greeting);
+    let owned = ret_returned_c_string();
+    assert_eq!(unsafe { CStr::from_ptr(owned) }, c"owned");
+    ret_string_free(owned);
+    ret_string_free(std::ptr::null_mut());
+}
+
+#[test]
+fn returns_null_for_none() {
+    let found = ret_returned_maybe(true);
+    assert_eq!(unsafe { CStr::from_ptr(found) }, c"found");
+    ret_string_free(found);
+    assert!(ret_returned_maybe(false).is_null());
+}
+
+#[test]
+fn returns_static_strings() {
+    let one = ret_returned_name(1);
+    assert_eq!(unsafe { CStr::from_ptr(one) }, c"one");
+    // Each string is copied once.
+    assert_eq!(ret_returned_name(3), one);
+    assert_ne!(ret_returned_name(0), one);
+    assert_eq!(unsafe { CStr::from_ptr(ret_returned_c_name()) }, c"static");
+}
+
+#[test]
+fn returns_null_for_interior_nul() {
+    assert!(ret_returned_nul().is_null());
+}
@@ -0,0 +1,34 @@
+use std::ffi::CStr;
+
+use coastal::coast;
+
+#[coast]
+pub fn truncated_owned() -> String {
+    "before\0after".to_owned()
+}
+
+#[coast]
+pub fn truncated_static() -> &'static str {
+    "start\0end"
+}
+
+coastal::api! {
+    prefix = "trunc";
+    interior_nul = "truncate";
+
+    fn truncated_owned;
+    fn truncated_static;
+}
+
+use coastal_wrappers::*;
+
+#[test]
+fn truncates_at_interior_nul() {
+    let owned = trunc_truncated_owned();
+    assert_eq!(unsafe { CStr::from_ptr(owned) }, c"before");
+    trunc_string_free(owned);
+    assert_eq!(
+        unsafe { CStr::from_ptr(trunc_truncated_static()) },
+        c"start"
+    );
+}
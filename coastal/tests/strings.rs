@@ -0,0 +1,106 @@
+use std::ffi::{c_char, CStr};
+use std::ptr;
+
+use coastal::coast;
+
+#[coast]
+pub fn text_len(text: &str) -> usize {
+    text.chars().count()
+}
+
+#[coast]
+pub fn text_bytes(text: &CStr) -> usize {
+    text.to_bytes().len()
+}
+
+#[coast]
+pub fn text_owned_len(mut text: String) -> usize {
+    text.push('!');
+    text.len()
+}
+
+#[coast]
+pub fn text_maybe_len(text: Option<&str>) -> i64 {
+    text.map_or(-1, |text| text.len() as i64)
+}
+
+#[coast]
+pub fn text_maybe_bytes(text: Option<&CStr>) -> i64 {
+    text.map_or(-1, |text| text.to_bytes().len() as i64)
+}
+
+#[coast]
+pub fn text_slice_len(#[coast(len)] text: &str) -> usize {
+    text.chars().count()
+}
+
+#[coast(error_value = -1)]
+pub fn text_strict_len(#[coast(invalid_utf8 = "error")] text: &str) -> i32 {
+    text.len() as i32
+}
+
+#[coast]
+pub fn text_lossy_len(#[coast(invalid_utf8 = "lossy")] text: &str) -> usize {
+    text.chars().count()
+}
+
+coastal::api! {
+    prefix = "str";
+    panic = "error";
+
+    fn text_len;
+    fn text_bytes;
+    fn text_owned_len;
+    fn text_maybe_len;
+    fn text_maybe_bytes;
+    fn text_slice_len;
+    fn text_strict_len;
+    fn text_lossy_len;
+}
+
+use coastal_wrappers::*;
+
+/// "é", then a byte that isn't valid UTF-8.
+const INVALID: &CStr = c"\xC3\xA9\xFF";
+
+#[test]
+fn converts_strings() {
+    assert_eq!(str_text_len(c"héllo".as_ptr()), 5);
+    assert_eq!(str_text_bytes(c"héllo".as_ptr()), 6);
+    assert_eq!(str_text_owned_len(c"hi".as_ptr()), 3);
+    // `&CStr` doesn't need to be UTF-8.
+    assert_eq!(str_text_bytes(INVALID.as_ptr()), 3);
+}
+
+#[test]
+fn converts_optional_strings() {
+    assert_eq!(str_text_maybe_len(c"abc".as_ptr()), 3);
+    assert_eq!(str_text_maybe_len(ptr::null()), -1);
+    assert_eq!(str_text_maybe_bytes(c"abcd".as_ptr()), 4);
+    assert_eq!(str_text_maybe_bytes(ptr::null()), -1);
+}
+
+#[test]
+fn converts_strings_with_lengths() {
+    // The string doesn't need a NUL terminator.
+    let text = b"hello world";
+    assert_eq!(str_text_slice_len(text.as_ptr().cast::<c_char>(), 5), 5);
+    assert_eq!(str_text_slice_len(ptr::null(), 0), 0);
+    assert_eq!(str_text_slice_len(ptr::null(), 1), 0);
+}
+
+#[test]
+fn rejects_null_strings() {
+    assert_eq!(str_text_len(ptr::null()), 0);
+    assert_eq!(str_text_bytes(ptr::null()), 0);
+    assert_eq!(str_text_owned_len(ptr::null()), 0);
+}
+
+#[test]
+fn handles_invalid_utf8() {
+    // The default policy panics, which returns the error value here.
+    assert_eq!(str_text_len(INVALID.as_ptr()), 0);
+    assert_eq!(str_text_strict_len(c"ok".as_ptr()), 2);
+    assert_eq!(str_text_strict_len(INVALID.as_ptr()), -1);
+    assert_eq!(str_text_lossy_len(INVALID.as_ptr()), 2);
+}
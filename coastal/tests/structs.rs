@@ -0,0 +1,122 @@
+use std::ptr;
+
+use coastal::coast;
+
+#[coast]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Extent {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Contains other structs by value.
+#[coast]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    pub offset: Extent,
+    pub size: Extent,
+    pub visible: bool,
+}
+
+#[coast]
+pub fn struct_extent(width: u32, height: u32) -> Extent {
+    Extent { width, height }
+}
+
+#[coast]
+pub fn struct_area(frame: Frame) -> u64 {
+    if frame.visible {
+        u64::from(frame.size.width) * u64::from(frame.size.height)
+    } else {
+        0
+    }
+}
+
+#[coast]
+pub fn struct_grow(frame: Frame, by: u32) -> Frame {
+    Frame {
+        size: Extent {
+            width: frame.size.width + by,
+            height: frame.size.height + by,
+        },
+        ..frame
+    }
+}
+
+#[coast]
+pub fn struct_right(frame: &Frame) -> u32 {
+    frame.offset.width + frame.size.width
+}
+
+#[coast]
+pub fn struct_hide(frame: &mut Frame) {
+    frame.visible = false;
+}
+
+coastal::api! {
+    prefix = "st";
+    panic = "error";
+
+    // Listed before the struct it contains, which the header still defines first.
+    struct Frame;
+    struct Extent;
+    fn struct_extent;
+    fn struct_area;
+    fn struct_grow;
+    fn struct_right;
+    fn struct_hide;
+}
+
+use coastal_wrappers::*;
+
+fn frame() -> Frame {
+    Frame {
+        offset: Extent {
+            width: 1,
+            height: 2,
+        },
+        size: Extent {
+            width: 3,
+            height: 4,
+        },
+        visible: true,
+    }
+}
+
+#[test]
+fn passes_structs_by_value() {
+    assert_eq!(
+        st_struct_extent(3, 4),
+        Extent {
+            width: 3,
+            height: 4
+        }
+    );
+    assert_eq!(st_struct_area(frame()), 12);
+    let grown = st_struct_grow(frame(), 1);
+    assert_eq!(grown.offset, frame().offset);
+    assert_eq!(
+        grown.size,
+        Extent {
+            width: 4,
+            height: 5
+        }
+    );
+}
+
+#[test]
+fn passes_structs_by_reference() {
+    let mut frame = frame();
+    assert_eq!(st_struct_right(&frame), 4);
+    st_struct_hide(&mut frame);
+    assert!(!frame.visible);
+    assert_eq!(st_struct_area(frame), 0);
+}
+
+#[test]
+fn rejects_null_structs() {
+    assert_eq!(st_struct_right(ptr::null()), 0);
+    st_struct_hide(ptr::null_mut());
+}
@@ -0,0 +1,111 @@
+use coastal::coast;
+
+#[coast(invalid = "error")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Token {
+    End,
+    Number(f64),
+    Range(i32, i32),
+    Word {
+        len: u32,
+        upper: bool,
+    },
+    /// Named after a keyword.
+    Ref(u32),
+}
+
+#[coast]
+pub fn tagged_parse(text_len: u32, kind: u8) -> Token {
+    match kind {
+        0 => Token::End,
+        1 => Token::Number(f64::from(text_len) / 2.0),
+        2 => Token::Range(0, text_len as i32),
+        5 => Token::Ref(text_len),
+        _ => Token::Word {
+            len: text_len,
+            upper: kind % 2 == 1,
+        },
+    }
+}
+
+#[coast]
+pub fn tagged_width(token: Token) -> i32 {
+    match token {
+        Token::End => 0,
+        Token::Number(_) => 1,
+        Token::Range(start, end) => end - start,
+        Token::Word { len, .. } => len as i32,
+        Token::Ref(id) => id as i32,
+    }
+}
+
+#[coast]
+pub fn tagged_shift(token: Token, by: i32) -> Token {
+    match token {
+        Token::Range(start, end) => Token::Range(start + by, end + by),
+        token => token,
+    }
+}
+
+coastal::api! {
+    prefix = "tagged";
+
+    enum Token;
+    fn tagged_parse;
+    fn tagged_width;
+    fn tagged_shift;
+}
+
+use coastal_wrappers::*;
+
+// The C types are private to the wrappers, so C values come from `tagged_parse`.
+
+#[test]
+fn returns_each_variant() {
+    assert_eq!(tagged_tagged_parse(4, 0).to_rust(), Ok(Token::End));
+    assert_eq!(tagged_tagged_parse(4, 1).to_rust(), Ok(Token::Number(2.0)));
+    assert_eq!(tagged_tagged_parse(4, 2).to_rust(), Ok(Token::Range(0, 4)));
+    assert_eq!(
+        tagged_tagged_parse(4, 3).to_rust(),
+        Ok(Token::Word {
+            len: 4,
+            upper: true
+        })
+    );
+}
+
+#[test]
+fn takes_each_variant() {
+    assert_eq!(tagged_tagged_width(tagged_tagged_parse(4, 0)), 0);
+    assert_eq!(tagged_tagged_width(tagged_tagged_parse(4, 1)), 1);
+    assert_eq!(tagged_tagged_width(tagged_tagged_parse(7, 2)), 7);
+    assert_eq!(tagged_tagged_width(tagged_tagged_parse(3, 4)), 3);
+    assert_eq!(tagged_tagged_width(tagged_tagged_parse(6, 5)), 6);
+    let shifted = tagged_tagged_shift(tagged_tagged_parse(7, 2), 10);
+    assert_eq!(shifted.to_rust(), Ok(Token::Range(10, 17)));
+}
+
+#[test]
+fn writes_the_tag_and_fields() {
+    let token = tagged_tagged_parse(2, 2);
+    assert_eq!(token.tag, 2);
+    assert_eq!(unsafe { token.data.range._0 }, 0);
+    assert_eq!(unsafe { token.data.range._1 }, 2);
+    let token = tagged_tagged_parse(5, 3);
+    assert_eq!(token.tag, 3);
+    assert_eq!(unsafe { token.data.word.len }, 5);
+    assert!(unsafe { token.data.word.upper });
+    let token = tagged_tagged_parse(6, 5);
+    assert_eq!(token.tag, 4);
+    assert_eq!(unsafe { token.data.ref_._0 }, 6);
+}
+
+#[test]
+fn returns_error_value_for_invalid_tags() {
+    let mut token = tagged_tagged_parse(4, 1);
+    token.tag = 9;
+    assert_eq!(tagged_tagged_width(token), 0);
+    let mut token = tagged_tagged_parse(4, 1);
+    token.tag = -1;
+    assert_eq!(tagged_tagged_shift(token, 1).tag, 0);
+}
@@ -0,0 +1,71 @@
+use std::ffi::CStr;
+use std::ptr;
+
+use coastal::coast;
+
+#[coast]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quotient {
+    pub whole: u32,
+    pub rest: u32,
+}
+
+#[coast]
+pub fn tuple_div_rem(a: u32, b: u32) -> (u32, u32) {
+    (a / b, a % b)
+}
+
+#[coast(out = "quotient, label")]
+pub fn tuple_describe(a: u32, b: u32) -> (Quotient, String) {
+    let quotient = Quotient {
+        whole: a / b,
+        rest: a % b,
+    };
+    (quotient, format!("{a} = {} * {b} + {}", a / b, a % b))
+}
+
+/// Has an argument with the same name as the first out-parameter.
+#[coast]
+pub fn tuple_swap(out_0: i8, out_1: i8) -> (i8, i8) {
+    (out_1, out_0)
+}
+
+coastal::api! {
+    prefix = "tuple";
+
+    struct Quotient;
+    fn tuple_div_rem;
+    fn tuple_describe;
+    fn tuple_swap;
+}
+
+use coastal_wrappers::*;
+
+#[test]
+fn writes_each_element() {
+    let (mut whole, mut rest) = (0, 0);
+    tuple_tuple_div_rem(7, 2, &mut whole, &mut rest);
+    assert_eq!((whole, rest), (3, 1));
+    tuple_tuple_div_rem(9, 4, ptr::null_mut(), &mut rest);
+    assert_eq!((whole, rest), (3, 1));
+}
+
+#[test]
+fn converts_each_element() {
+    let mut quotient = Quotient { whole: 0, rest: 0 };
+    let mut label = ptr::null_mut();
+    tuple_tuple_describe(7, 2, &mut quotient, &mut label);
+    assert_eq!(quotient, Quotient { whole: 3, rest: 1 });
+    assert_eq!(unsafe { CStr::from_ptr(label) }, c"7 = 3 * 2 + 1");
+    tuple_string_free(label);
+    // Strings aren't allocated for null out-parameters.
+    tuple_tuple_describe(7, 2, ptr::null_mut(), ptr::null_mut());
+}
+
+#[test]
+fn renames_out_parameters_after_arguments() {
+    let (mut a, mut b) = (0, 0);
+    tuple_tuple_swap(1, 2, &mut a, &mut b);
+    assert_eq!((a, b), (2, 1));
+}
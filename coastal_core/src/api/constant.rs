@@ -1,41 +1,101 @@
-use std::io::Write;
-
 use convert_case::{Case, Casing};
+use proc_macro2::Span;
 use serde::{Deserialize, Serialize};
+use syn::Error;
+
+use crate::format_err;
 
-use super::State;
+use super::{ConstExpr, ItemOptions, Library, Number, State};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Constant {
     pub name: String,
     pub value: ConstantValue,
+    #[serde(default)]
+    pub options: ItemOptions,
 }
 
 impl Constant {
-    pub fn c_header(&self, f: &mut impl Write) -> Result<(), std::io::Error> {
-        let name = format!("PREFIX_{}", self.name.to_case(Case::ScreamingSnake));
-        match &self.value {
-            ConstantValue::CChar(val) => {
-                writeln!(f, "#define {name} {:?}", char::from(*val))
-            }
-            ConstantValue::I8(val) => writeln!(f, "#define {name} ((int8_t){val})"),
-            ConstantValue::I16(val) => writeln!(f, "#define {name} ((int16_t){val})"),
-            ConstantValue::I32(val) => writeln!(f, "#define {name} {val}"),
-            ConstantValue::I64(val) => writeln!(f, "#define {name} {val}ll"),
-            ConstantValue::U8(val) => writeln!(f, "#define {name} ((uint8_t){val}u)"),
-            ConstantValue::U16(val) => writeln!(f, "#define {name} ((uint16_t){val}u)"),
-            ConstantValue::U32(val) => writeln!(f, "#define {name} {val}u"),
-            ConstantValue::U64(val) => writeln!(f, "#define {name} {val}ull"),
-            ConstantValue::F32(val) => writeln!(f, "#define {name} {val}f"),
-            ConstantValue::F64(val) => writeln!(f, "#define {name} {val}"),
-            ConstantValue::Str(val) => writeln!(f, "#define {name} {val:?}"),
-            ConstantValue::Bytes(val) => {
-                let string_constant =
-                    String::from_utf8(val.iter().copied().flat_map(u8::escape_ascii).collect())
-                        .expect("valid utf8");
-                writeln!(f, "#define {name} \"{string_constant}\"")
+    /// Returns the prefixed C name of the constant.
+    pub fn c_name(&self, lib: &Library) -> String {
+        if let Some(name) = &self.options.name {
+            return name.clone();
+        }
+        format!(
+            "{}{}",
+            lib.constant_prefix,
+            self.name.to_case(Case::ScreamingSnake)
+        )
+    }
+
+    /// Returns the C definition of the constant in the library's style, after its comment.
+    ///
+    /// `__int128` isn't standard C, so definitions that use it are skipped by compilers without
+    /// `__SIZEOF_INT128__`.
+    pub fn c_definition(&self, lib: &Library) -> Result<String, Error> {
+        let definition = self.c_value_definition(lib)?;
+        let needs_int128 = self.needs_int128(lib, &definition)?;
+        let definition = match self.options.c_comment(lib, None, "", &[]) {
+            Some(comment) => format!("{comment}\n{definition}"),
+            None => definition,
+        };
+        Ok(if needs_int128 {
+            format!("#ifdef __SIZEOF_INT128__\n{definition}\n#endif")
+        } else {
+            definition
+        })
+    }
+
+    /// Returns true if the C `definition` uses `__int128`, or refers to a constant that does.
+    fn needs_int128(&self, lib: &Library, definition: &str) -> Result<bool, Error> {
+        if definition.contains("__int128") {
+            return Ok(true);
+        }
+        if let (ConstantStyle::Define, ConstantValue::Expr(_, expr)) =
+            (lib.constant_style, &self.value)
+        {
+            for name in expr.references() {
+                let constant = lib.constant(name)?;
+                if constant.needs_int128(lib, &constant.c_value_definition(lib)?)? {
+                    return Ok(true);
+                }
             }
         }
+        Ok(false)
+    }
+
+    /// Returns the C definition of the constant in the library's style.
+    fn c_value_definition(&self, lib: &Library) -> Result<String, Error> {
+        let name = self.c_name(lib);
+        // Expressions are always evaluated, so that overflow is caught even for `#define`.
+        let value = self.value.resolve(lib)?;
+        if let (ConstantStyle::Define, ConstantValue::Expr(ty, expr)) =
+            (lib.constant_style, &self.value)
+        {
+            return Ok(format!(
+                "#define {name} (({}){})",
+                value.c_type(),
+                expr.c_expr(ty, lib)?
+            ));
+        }
+        Ok(match lib.constant_style {
+            ConstantStyle::Define => format!("#define {name} {}", value.c_literal()),
+            ConstantStyle::Enum if value.fits_enum() => {
+                format!("enum {{ {name} = {} }};", value.c_literal())
+            }
+            ConstantStyle::StaticConst | ConstantStyle::Enum => match &value {
+                ConstantValue::Str(_) | ConstantValue::Bytes(_) => format!(
+                    "static const {} {name}[] = {};",
+                    value.c_type(),
+                    value.c_literal()
+                ),
+                _ => format!(
+                    "static const {} {name} = {};",
+                    value.c_type(),
+                    value.c_literal()
+                ),
+            },
+        })
     }
 }
 
@@ -43,19 +103,487 @@ impl State for Constant {
     const TYPE_NAME: &'static str = "coastal.constant";
 }
 
+/// How constants are written to the C header.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConstantStyle {
+    /// `#define NAME value`
+    #[default]
+    Define,
+    /// `static const type NAME = value;`
+    StaticConst,
+    /// `enum { NAME = value };` for integers that fit in an `int`, otherwise `static const`.
+    Enum,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ConstantValue {
+    Bool(bool),
     CChar(u8),
+    /// A Unicode scalar value, written to C as a `uint32_t` code point.
+    Char(char),
     I8(i8),
     I16(i16),
     I32(i32),
     I64(i64),
-    U8(i8),
-    U16(i16),
-    U32(i32),
-    U64(i64),
+    I128(i128),
+    Isize(i64),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    Usize(u64),
     F32(f32),
     F64(f64),
     Str(String),
     Bytes(Vec<u8>),
+    /// An expression of the named primitive type, evaluated once all constants are known.
+    Expr(String, ConstExpr),
+}
+
+impl ConstantValue {
+    /// Returns the C type of the value, or the element type for strings and bytes.
+    pub fn c_type(&self) -> &'static str {
+        c_type_name(self.rust_type()).expect("constant types have C equivalents")
+    }
+
+    /// Returns the Rust type of the value, such as `u32` or `&str`.
+    pub fn rust_type(&self) -> &str {
+        match self {
+            ConstantValue::Bool(_) => "bool",
+            ConstantValue::CChar(_) => "c_char",
+            ConstantValue::Char(_) => "char",
+            ConstantValue::I8(_) => "i8",
+            ConstantValue::I16(_) => "i16",
+            ConstantValue::I32(_) => "i32",
+            ConstantValue::I64(_) => "i64",
+            ConstantValue::I128(_) => "i128",
+            ConstantValue::Isize(_) => "isize",
+            ConstantValue::U8(_) => "u8",
+            ConstantValue::U16(_) => "u16",
+            ConstantValue::U32(_) => "u32",
+            ConstantValue::U64(_) => "u64",
+            ConstantValue::U128(_) => "u128",
+            ConstantValue::Usize(_) => "usize",
+            ConstantValue::F32(_) => "f32",
+            ConstantValue::F64(_) => "f64",
+            ConstantValue::Str(_) => "&str",
+            ConstantValue::Bytes(_) => "&[u8]",
+            ConstantValue::Expr(ty, _) => ty,
+        }
+    }
+
+    /// Converts the result of evaluating an expression to a value of type `ty`.
+    pub fn from_number(ty: &str, number: Number) -> Result<ConstantValue, Error> {
+        let out_of_range = || format_err!("Coastal constant value is out of range for '{ty}'");
+        Ok(match (ty, number) {
+            ("bool", Number::Bool(val)) => ConstantValue::Bool(val),
+            ("i8", Number::Int(val)) => {
+                ConstantValue::I8(val.try_into().map_err(|_| out_of_range())?)
+            }
+            ("i16", Number::Int(val)) => {
+                ConstantValue::I16(val.try_into().map_err(|_| out_of_range())?)
+            }
+            ("i32", Number::Int(val)) => {
+                ConstantValue::I32(val.try_into().map_err(|_| out_of_range())?)
+            }
+            ("i64", Number::Int(val)) => {
+                ConstantValue::I64(val.try_into().map_err(|_| out_of_range())?)
+            }
+            ("i128", Number::Int(val)) => ConstantValue::I128(val),
+            ("isize", Number::Int(val)) => {
+                ConstantValue::Isize(val.try_into().map_err(|_| out_of_range())?)
+            }
+            ("u8", Number::Int(val)) => {
+                ConstantValue::U8(val.try_into().map_err(|_| out_of_range())?)
+            }
+            ("u16", Number::Int(val)) => {
+                ConstantValue::U16(val.try_into().map_err(|_| out_of_range())?)
+            }
+            ("u32", Number::Int(val)) => {
+                ConstantValue::U32(val.try_into().map_err(|_| out_of_range())?)
+            }
+            ("u64", Number::Int(val)) => {
+                ConstantValue::U64(val.try_into().map_err(|_| out_of_range())?)
+            }
+            ("u128", Number::Int(val)) => {
+                ConstantValue::U128(val.try_into().map_err(|_| out_of_range())?)
+            }
+            ("usize", Number::Int(val)) => {
+                ConstantValue::Usize(val.try_into().map_err(|_| out_of_range())?)
+            }
+            ("f32", Number::Float(val)) => ConstantValue::F32(val as f32),
+            ("f64", Number::Float(val)) => ConstantValue::F64(val),
+            _ => return Err(format_err!("Coastal can't convert this value to '{ty}'")),
+        })
+    }
+
+    /// Returns the value for use in constant expressions, if it's a number or bool.
+    pub fn number(&self) -> Option<Number> {
+        Some(match self {
+            ConstantValue::Bool(val) => Number::Bool(*val),
+            ConstantValue::I8(val) => Number::Int((*val).into()),
+            ConstantValue::I16(val) => Number::Int((*val).into()),
+            ConstantValue::I32(val) => Number::Int((*val).into()),
+            ConstantValue::I64(val) | ConstantValue::Isize(val) => Number::Int((*val).into()),
+            ConstantValue::I128(val) => Number::Int(*val),
+            ConstantValue::U8(val) => Number::Int((*val).into()),
+            ConstantValue::U16(val) => Number::Int((*val).into()),
+            ConstantValue::U32(val) => Number::Int((*val).into()),
+            ConstantValue::U64(val) | ConstantValue::Usize(val) => Number::Int((*val).into()),
+            ConstantValue::U128(val) => Number::Int((*val).try_into().ok()?),
+            ConstantValue::F32(val) => Number::Float((*val).into()),
+            ConstantValue::F64(val) => Number::Float(*val),
+            _ => return None,
+        })
+    }
+
+    /// Returns the value with any expression evaluated.
+    pub fn resolve(&self, lib: &Library) -> Result<ConstantValue, Error> {
+        match self {
+            ConstantValue::Expr(ty, expr) => {
+                ConstantValue::from_number(ty, expr.evaluate(ty, lib)?)
+            }
+            value => Ok(value.clone()),
+        }
+    }
+
+    /// Returns a C expression for the value.
+    ///
+    /// Minimum values use the `<stdint.h>` limit macros, since C parses `-2147483648` as the
+    /// negation of a literal that doesn't fit in an `int`.
+    pub fn c_literal(&self) -> String {
+        match self {
+            ConstantValue::Bool(val) => format!("{val}"),
+            ConstantValue::CChar(val) => c_char_literal(*val),
+            ConstantValue::Char(val) => format!("UINT32_C(0x{:X})", u32::from(*val)),
+            ConstantValue::I8(val) => format!("((int8_t){val})"),
+            ConstantValue::I16(val) => format!("((int16_t){val})"),
+            ConstantValue::I32(i32::MIN) => "INT32_MIN".to_owned(),
+            ConstantValue::I32(val) => format!("INT32_C({val})"),
+            ConstantValue::I64(i64::MIN) => "INT64_MIN".to_owned(),
+            ConstantValue::I64(val) => format!("INT64_C({val})"),
+            ConstantValue::I128(val) => match i64::try_from(*val) {
+                Ok(i64::MIN) => "((__int128)INT64_MIN)".to_owned(),
+                Ok(val) => format!("((__int128)INT64_C({val}))"),
+                Err(_) => format!("((__int128){})", c_u128_literal(*val as u128)),
+            },
+            ConstantValue::Isize(i64::MIN) => "INTPTR_MIN".to_owned(),
+            ConstantValue::Isize(val) => format!("((intptr_t)INT64_C({val}))"),
+            ConstantValue::U8(val) => format!("((uint8_t){val}u)"),
+            ConstantValue::U16(val) => format!("((uint16_t){val}u)"),
+            ConstantValue::U32(val) => format!("UINT32_C({val})"),
+            ConstantValue::U64(val) => format!("UINT64_C({val})"),
+            ConstantValue::U128(val) => c_u128_literal(*val),
+            ConstantValue::Usize(u64::MAX) => "SIZE_MAX".to_owned(),
+            ConstantValue::Usize(val) => format!("((size_t)UINT64_C({val}))"),
+            ConstantValue::F32(val) if val.is_sign_negative() => format!("({val:?}f)"),
+            ConstantValue::F32(val) => format!("{val:?}f"),
+            ConstantValue::F64(val) if val.is_sign_negative() => format!("({val:?})"),
+            ConstantValue::F64(val) => format!("{val:?}"),
+            ConstantValue::Str(val) => c_string_literal(val.as_bytes()),
+            ConstantValue::Bytes(val) => c_string_literal(val),
+            ConstantValue::Expr(..) => unreachable!("expressions are resolved first"),
+        }
+    }
+
+    /// Returns true if the value can be an enumeration constant, which must fit in an `int`.
+    fn fits_enum(&self) -> bool {
+        match self {
+            ConstantValue::Bool(_)
+            | ConstantValue::CChar(_)
+            | ConstantValue::Char(_)
+            | ConstantValue::I8(_)
+            | ConstantValue::I16(_)
+            | ConstantValue::I32(_)
+            | ConstantValue::U8(_)
+            | ConstantValue::U16(_) => true,
+            ConstantValue::I64(val) | ConstantValue::Isize(val) => i32::try_from(*val).is_ok(),
+            ConstantValue::I128(val) => i32::try_from(*val).is_ok(),
+            ConstantValue::U32(val) => i32::try_from(*val).is_ok(),
+            ConstantValue::U64(val) | ConstantValue::Usize(val) => i32::try_from(*val).is_ok(),
+            ConstantValue::U128(val) => i32::try_from(*val).is_ok(),
+            _ => false,
+        }
+    }
+}
+
+/// Returns the C type for a Rust constant type, or the element type for strings and bytes.
+pub(crate) fn c_type_name(ty: &str) -> Option<&'static str> {
+    Some(match ty {
+        "bool" => "bool",
+        "c_char" => "char",
+        "char" => "uint32_t",
+        "i8" => "int8_t",
+        "i16" => "int16_t",
+        "i32" => "int32_t",
+        "i64" => "int64_t",
+        "i128" => "__int128",
+        "isize" => "intptr_t",
+        "u8" => "uint8_t",
+        "u16" => "uint16_t",
+        "u32" => "uint32_t",
+        "u64" => "uint64_t",
+        "u128" => "unsigned __int128",
+        "usize" => "size_t",
+        "f32" => "float",
+        "f64" => "double",
+        "&str" => "char",
+        "&[u8]" => "char",
+        _ => return None,
+    })
+}
+
+/// Formats a 128-bit value, which C can only build from 64-bit halves.
+fn c_u128_literal(val: u128) -> String {
+    let (high, low) = ((val >> 64) as u64, val as u64);
+    if high == 0 {
+        format!("((unsigned __int128)UINT64_C({low}))")
+    } else {
+        format!("(((unsigned __int128)UINT64_C({high}) << 64) | UINT64_C({low}))")
+    }
+}
+
+/// Formats a byte as a C character literal.
+fn c_char_literal(b: u8) -> String {
+    match b {
+        b'\'' | b'\\' => format!("'\\{}'", char::from(b)),
+        b' '..=b'~' => format!("'{}'", char::from(b)),
+        _ => format!("'\\{b:03o}'"),
+    }
+}
+
+/// Formats bytes as a C string literal, using octal escapes since hex escapes in C consume any
+/// number of following hex digits.
+fn c_string_literal(bytes: &[u8]) -> String {
+    let mut literal = String::from("\"");
+    for &b in bytes {
+        match b {
+            b'"' | b'\\' | b'?' => {
+                literal.push('\\');
+                literal.push(char::from(b));
+            }
+            b' '..=b'~' => literal.push(char::from(b)),
+            _ => literal.push_str(&format!("\\{b:03o}")),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::BinaryOp;
+
+    fn definition(lib: &Library, name: &str, value: ConstantValue) -> Result<String, String> {
+        let constant = Constant {
+            name: name.to_owned(),
+            value,
+            options: Default::default(),
+        };
+        constant.c_definition(lib).map_err(|err| err.to_string())
+    }
+
+    fn lib(style: ConstantStyle) -> Library {
+        let mut lib = Library::new();
+        lib.set_prefix("test");
+        lib.constant_style = style;
+        lib
+    }
+
+    #[test]
+    fn writes_integer_literals() {
+        assert_eq!(ConstantValue::Bool(true).c_literal(), "true");
+        assert_eq!(ConstantValue::I8(-128).c_literal(), "((int8_t)-128)");
+        assert_eq!(ConstantValue::I32(-5).c_literal(), "INT32_C(-5)");
+        assert_eq!(ConstantValue::I32(i32::MIN).c_literal(), "INT32_MIN");
+        assert_eq!(ConstantValue::I64(i64::MIN).c_literal(), "INT64_MIN");
+        assert_eq!(ConstantValue::Isize(i64::MIN).c_literal(), "INTPTR_MIN");
+        assert_eq!(ConstantValue::U8(255).c_literal(), "((uint8_t)255u)");
+        assert_eq!(ConstantValue::U64(1).c_literal(), "UINT64_C(1)");
+        assert_eq!(ConstantValue::Usize(u64::MAX).c_literal(), "SIZE_MAX");
+        assert_eq!(
+            ConstantValue::Usize(16).c_literal(),
+            "((size_t)UINT64_C(16))"
+        );
+    }
+
+    #[test]
+    fn writes_128_bit_literals() {
+        assert_eq!(
+            ConstantValue::U128(5).c_literal(),
+            "((unsigned __int128)UINT64_C(5))"
+        );
+        assert_eq!(
+            ConstantValue::U128(1 << 64 | 5).c_literal(),
+            "(((unsigned __int128)UINT64_C(1) << 64) | UINT64_C(5))"
+        );
+        assert_eq!(
+            ConstantValue::I128(-1).c_literal(),
+            "((__int128)INT64_C(-1))"
+        );
+        assert_eq!(
+            ConstantValue::I128(i64::MIN.into()).c_literal(),
+            "((__int128)INT64_MIN)"
+        );
+        assert_eq!(
+            ConstantValue::I128(1 << 64).c_literal(),
+            "((__int128)(((unsigned __int128)UINT64_C(1) << 64) | UINT64_C(0)))"
+        );
+    }
+
+    #[test]
+    fn writes_float_literals() {
+        assert_eq!(ConstantValue::F32(1.0).c_literal(), "1.0f");
+        assert_eq!(ConstantValue::F32(-0.5).c_literal(), "(-0.5f)");
+        assert_eq!(ConstantValue::F64(2.0).c_literal(), "2.0");
+        assert_eq!(ConstantValue::F64(-0.0).c_literal(), "(-0.0)");
+        assert_eq!(ConstantValue::F64(1e300).c_literal(), "1e300");
+    }
+
+    #[test]
+    fn writes_character_literals() {
+        assert_eq!(ConstantValue::CChar(b'a').c_literal(), "'a'");
+        assert_eq!(ConstantValue::CChar(b'\'').c_literal(), "'\\''");
+        assert_eq!(ConstantValue::CChar(b'\\').c_literal(), "'\\\\'");
+        assert_eq!(ConstantValue::CChar(0).c_literal(), "'\\000'");
+        assert_eq!(ConstantValue::Char('é').c_literal(), "UINT32_C(0xE9)");
+    }
+
+    #[test]
+    fn writes_string_literals() {
+        assert_eq!(
+            ConstantValue::Str("say \"hi\"??/\n".to_owned()).c_literal(),
+            "\"say \\\"hi\\\"\\?\\?/\\012\""
+        );
+        // An escape followed by a digit stays separate.
+        assert_eq!(
+            ConstantValue::Bytes(vec![0xFF, b'1']).c_literal(),
+            "\"\\3771\""
+        );
+        assert_eq!(
+            ConstantValue::Str("é".to_owned()).c_literal(),
+            "\"\\303\\251\""
+        );
+    }
+
+    #[test]
+    fn writes_definitions_in_each_style() {
+        let value = || ConstantValue::U32(5);
+        assert_eq!(
+            definition(&lib(ConstantStyle::Define), "max_len", value()),
+            Ok("#define TEST_MAX_LEN UINT32_C(5)".to_owned())
+        );
+        assert_eq!(
+            definition(&lib(ConstantStyle::StaticConst), "max_len", value()),
+            Ok("static const uint32_t TEST_MAX_LEN = UINT32_C(5);".to_owned())
+        );
+        assert_eq!(
+            definition(&lib(ConstantStyle::Enum), "max_len", value()),
+            Ok("enum { TEST_MAX_LEN = UINT32_C(5) };".to_owned())
+        );
+        // Values that don't fit in an `int` can't be enumeration constants.
+        assert_eq!(
+            definition(
+                &lib(ConstantStyle::Enum),
+                "big",
+                ConstantValue::U64(1 << 40)
+            ),
+            Ok("static const uint64_t TEST_BIG = UINT64_C(1099511627776);".to_owned())
+        );
+        assert_eq!(
+            definition(
+                &lib(ConstantStyle::StaticConst),
+                "greeting",
+                ConstantValue::Str("hi".to_owned())
+            ),
+            Ok("static const char TEST_GREETING[] = \"hi\";".to_owned())
+        );
+    }
+
+    #[test]
+    fn writes_expression_definitions() {
+        let product = |left, right| {
+            ConstExpr::Binary(
+                BinaryOp::Mul,
+                Box::new(ConstExpr::Int(left)),
+                Box::new(ConstExpr::Int(right)),
+            )
+        };
+        let value = || ConstantValue::Expr("u32".to_owned(), product(2, 3));
+        assert_eq!(
+            definition(&lib(ConstantStyle::Define), "size", value()),
+            Ok("#define TEST_SIZE ((uint32_t)(UINT32_C(2) * UINT32_C(3)))".to_owned())
+        );
+        assert_eq!(
+            definition(&lib(ConstantStyle::StaticConst), "size", value()),
+            Ok("static const uint32_t TEST_SIZE = UINT32_C(6);".to_owned())
+        );
+        // Overflow is caught even though `#define` writes the expression.
+        assert_eq!(
+            definition(
+                &lib(ConstantStyle::Define),
+                "huge",
+                ConstantValue::Expr("u8".to_owned(), product(16, 16))
+            ),
+            Err("Coastal constant expression overflows 'u8'".to_owned())
+        );
+        let infinity = ConstExpr::Binary(
+            BinaryOp::Div,
+            Box::new(ConstExpr::Float(1.0)),
+            Box::new(ConstExpr::Float(0.0)),
+        );
+        assert_eq!(
+            definition(
+                &lib(ConstantStyle::Define),
+                "infinity",
+                ConstantValue::Expr("f64".to_owned(), infinity)
+            ),
+            Err("Coastal constant expression is infinite or NaN as 'f64'".to_owned())
+        );
+    }
+
+    #[test]
+    fn guards_128_bit_definitions() {
+        let mut lib = lib(ConstantStyle::Define);
+        lib.constants.push(Constant {
+            name: "WIDE".to_owned(),
+            value: ConstantValue::U128(5),
+            options: Default::default(),
+        });
+        assert_eq!(
+            definition(&lib, "wide", ConstantValue::U128(5)),
+            Ok("#ifdef __SIZEOF_INT128__\n\
+                #define TEST_WIDE ((unsigned __int128)UINT64_C(5))\n\
+                #endif"
+                .to_owned())
+        );
+        // Referring to a 128-bit constant needs it to be defined, even without a 128-bit type.
+        let narrowed = ConstExpr::Cast(
+            Box::new(ConstExpr::Const("WIDE".to_owned())),
+            "u64".to_owned(),
+        );
+        assert_eq!(
+            definition(
+                &lib,
+                "narrow",
+                ConstantValue::Expr("u64".to_owned(), narrowed.clone())
+            ),
+            Ok("#ifdef __SIZEOF_INT128__\n\
+                #define TEST_NARROW ((uint64_t)((uint64_t)TEST_WIDE))\n\
+                #endif"
+                .to_owned())
+        );
+        // Other styles write the value, which doesn't need the constant.
+        lib.constant_style = ConstantStyle::StaticConst;
+        assert_eq!(
+            definition(
+                &lib,
+                "narrow",
+                ConstantValue::Expr("u64".to_owned(), narrowed)
+            ),
+            Ok("static const uint64_t TEST_NARROW = UINT64_C(5);".to_owned())
+        );
+    }
 }
@@ -0,0 +1,390 @@
+use super::Library;
+
+/// Returns `text` as a Doxygen `/** ... */` comment with each line indented by `indent`, or
+/// `None` if there's nothing to say.
+pub(crate) fn doxygen(text: &str, indent: &str) -> Option<String> {
+    // A `*/` would end the comment early.
+    let text = text.trim().replace("*/", "* /");
+    if text.is_empty() {
+        return None;
+    }
+    if !text.contains('\n') {
+        return Some(format!("{indent}/** {text} */"));
+    }
+    let mut comment = format!("{indent}/**\n");
+    for line in text.lines() {
+        match line.trim_end() {
+            "" => comment.push_str(&format!("{indent} *\n")),
+            line => comment.push_str(&format!("{indent} * {line}\n")),
+        }
+    }
+    comment.push_str(&format!("{indent} */"));
+    Some(comment)
+}
+
+/// Translates the Markdown of a doc comment for the header.
+///
+/// Intra-doc links and code spans that name exported items become their C names, and code
+/// blocks are dropped unless they're marked as C, since Rust examples don't help C users.
+/// `self_type` is the type that `Self` refers to.
+pub(crate) fn c_doc(lib: &Library, text: &str, self_type: Option<&str>) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    // Whether to keep the code block we're in, if any.
+    let mut code_block: Option<bool> = None;
+    for line in text.lines() {
+        if let Some(lang) = line.trim_start().strip_prefix("```") {
+            let keep = match code_block.take() {
+                Some(keep) => keep,
+                None => {
+                    let keep = matches!(lang.trim(), "c" | "text");
+                    code_block = Some(keep);
+                    keep
+                }
+            };
+            if keep {
+                lines.push(line.to_owned());
+            }
+            continue;
+        }
+        match code_block {
+            Some(true) => lines.push(line.to_owned()),
+            Some(false) => (),
+            None => lines.push(c_doc_line(lib, line, self_type)),
+        }
+    }
+    // Dropping code blocks can leave several blank lines in a row.
+    lines.dedup_by(|a, b| a.trim().is_empty() && b.trim().is_empty());
+    lines.join("\n").trim().to_owned()
+}
+
+/// Translates the links and code spans in a line of Markdown.
+fn c_doc_line(lib: &Library, line: &str, self_type: Option<&str>) -> String {
+    let mut translated = String::new();
+    let mut rest = line;
+    while let Some(start) = rest.find(['[', '`']) {
+        translated.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let (source, replacement) = if rest.starts_with('`') {
+            code_span(lib, rest, self_type)
+        } else {
+            link(lib, rest, self_type)
+        };
+        translated.push_str(replacement.as_deref().unwrap_or(source));
+        rest = &rest[source.len()..];
+    }
+    translated.push_str(rest);
+    translated
+}
+
+/// Translates a code span at the start of `text`, returning its source and the C code span if it
+/// names an exported item.
+fn code_span<'a>(
+    lib: &Library,
+    text: &'a str,
+    self_type: Option<&str>,
+) -> (&'a str, Option<String>) {
+    let Some(end) = text[1..].find('`') else {
+        return (&text[..1], None);
+    };
+    let source = &text[..end + 2];
+    let code = &source[1..source.len() - 1];
+    let replacement = c_item_name(lib, code, self_type).map(|(name, _)| {
+        let call = if code.ends_with("()") { "()" } else { "" };
+        format!("`{name}{call}`")
+    });
+    (source, replacement)
+}
+
+/// Translates an intra-doc link like `[Polyline::push]` or `[the sum](sum)` at the start of
+/// `text`, returning its source and the C name if it names an exported item.
+fn link<'a>(lib: &Library, text: &'a str, self_type: Option<&str>) -> (&'a str, Option<String>) {
+    let Some(end) = text.find(']') else {
+        return (&text[..1], None);
+    };
+    let label = &text[1..end];
+    let after = &text[end + 1..];
+    let (source, target) = match after.chars().next() {
+        Some(open @ ('(' | '[')) => {
+            let close = if open == '(' { ')' } else { ']' };
+            match after.find(close) {
+                Some(len) if len > 1 => (&text[..end + 2 + len], &after[1..len]),
+                Some(len) => (&text[..end + 2 + len], label),
+                None => (&text[..end + 1], label),
+            }
+        }
+        _ => (&text[..end + 1], label),
+    };
+    let Some((name, is_function)) = c_item_name(lib, target.trim_matches('`'), self_type) else {
+        // Leave the brackets of anything else, like `[0, 1]`, so the code spans inside are
+        // still translated.
+        return (&text[..1], None);
+    };
+    let name = if is_function {
+        format!("{name}()")
+    } else {
+        name
+    };
+    let plain = |s: &str| s.trim_matches('`').trim_end_matches("()").to_owned();
+    let replacement = if plain(label) == plain(target) {
+        name
+    } else {
+        format!("{} ({name})", c_doc_line(lib, label, self_type))
+    };
+    (source, Some(replacement))
+}
+
+/// Returns the C name of the exported item at a Rust path like `Polyline::push` or
+/// `crate::KEY_LEN`, and whether it's a function.
+fn c_item_name(lib: &Library, path: &str, self_type: Option<&str>) -> Option<(String, bool)> {
+    // Skip disambiguators like `fn@sum` and `sum()`.
+    let path = path.split_once('@').map_or(path, |(_, path)| path);
+    let path = path.trim_end_matches("()");
+    let mut segments: Vec<&str> = path
+        .split("::")
+        .skip_while(|s| matches!(*s, "crate" | "self" | "super"))
+        .map(|s| match (s, self_type) {
+            ("Self", Some(self_type)) => self_type,
+            (s, _) => s,
+        })
+        .collect();
+    if let [.., ty, item] = segments[..] {
+        if let Some(function) = lib
+            .functions
+            .iter()
+            .find(|f| f.self_type.as_deref() == Some(ty) && f.name == item)
+        {
+            return Some((function.c_name(lib), true));
+        }
+        if let Some(e) = lib
+            .enums
+            .iter()
+            .find(|e| e.name == ty && e.variants.iter().any(|v| v.name == item))
+        {
+            return Some((e.c_variant_name(lib, item), false));
+        }
+        // Anything else is only an item if the rest of the path is modules.
+        if !ty.starts_with(char::is_lowercase) {
+            return None;
+        }
+    }
+    let name = segments.pop()?;
+    if let Some(function) = lib
+        .functions
+        .iter()
+        .find(|f| f.self_type.is_none() && f.name == name)
+    {
+        return Some((function.c_name(lib), true));
+    }
+    if let Some(s) = lib.structs.iter().find(|s| s.name == name) {
+        return Some((s.c_name(lib), false));
+    }
+    if let Some(e) = lib.enums.iter().find(|e| e.name == name) {
+        return Some((e.c_name(lib), false));
+    }
+    let constant = lib.constants.iter().find(|c| c.name == name)?;
+    Some((constant.c_name(lib), false))
+}
+
+/// Splits the `# Arguments` section out of the documentation of a function, returning the rest
+/// and the description of each argument from items like ``* `name` - description``.
+pub(crate) fn split_arguments(doc: &str) -> (String, Vec<(String, String)>) {
+    let mut rest: Vec<&str> = Vec::new();
+    let mut arguments: Vec<(String, String)> = Vec::new();
+    let mut in_arguments = false;
+    let mut in_code_block = false;
+    for line in doc.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+        } else if let Some(heading) = line.strip_prefix('#').filter(|_| !in_code_block) {
+            in_arguments = heading
+                .trim_start_matches('#')
+                .trim()
+                .eq_ignore_ascii_case("arguments");
+            if in_arguments {
+                continue;
+            }
+        }
+        if !in_arguments {
+            rest.push(line);
+            continue;
+        }
+        let item = line
+            .trim_start()
+            .strip_prefix(['*', '-'])
+            .and_then(|item| item.trim_start().strip_prefix('`'))
+            .and_then(|item| item.split_once('`'));
+        match (item, arguments.last_mut()) {
+            (Some((name, description)), _) => {
+                let description = description.trim_start().trim_start_matches(['-', ':']);
+                arguments.push((name.to_owned(), description.trim().to_owned()));
+            }
+            // Continuation lines of an item are indented.
+            (None, Some((_, description))) if !line.trim().is_empty() => {
+                description.push(' ');
+                description.push_str(line.trim());
+            }
+            (None, _) => (),
+        }
+    }
+    (rest.join("\n").trim().to_owned(), arguments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{Constant, ConstantValue, Enum, Struct, Variant};
+
+    fn lib() -> Library {
+        let mut lib = Library::new();
+        lib.set_prefix("geo");
+        for function in [
+            r#"{ "name": "sum", "return_type": "f64", "arguments": [] }"#,
+            r#"{ "name": "push", "return_type": "()", "arguments": [], "self_type": "Polyline" }"#,
+        ] {
+            lib.functions.push(serde_json::from_str(function).unwrap());
+        }
+        lib.structs.push(Struct {
+            name: "Polyline".to_owned(),
+            fields: Vec::new(),
+            opaque: true,
+            doc: None,
+        });
+        lib.enums.push(Enum {
+            name: "Corner".to_owned(),
+            repr: None,
+            variants: vec![Variant {
+                name: "TopLeft".to_owned(),
+                value: 0,
+                fields: Vec::new(),
+                doc: None,
+            }],
+            default: None,
+            invalid: None,
+            error: false,
+            doc: None,
+        });
+        lib.constants.push(Constant {
+            name: "KEY_LEN".to_owned(),
+            value: ConstantValue::U32(32),
+            options: Default::default(),
+        });
+        lib
+    }
+
+    fn c_doc_of(text: &str) -> String {
+        c_doc(&lib(), text, Some("Polyline"))
+    }
+
+    #[test]
+    fn writes_doxygen_comments() {
+        assert_eq!(doxygen("  ", ""), None);
+        assert_eq!(
+            doxygen("Adds one.", "    "),
+            Some("    /** Adds one. */".to_owned())
+        );
+        assert_eq!(
+            doxygen("Adds one.\n\nNever fails.", ""),
+            Some("/**\n * Adds one.\n *\n * Never fails.\n */".to_owned())
+        );
+        assert_eq!(
+            doxygen("Matches `*/`.", ""),
+            Some("/** Matches `* /`. */".to_owned())
+        );
+    }
+
+    #[test]
+    fn translates_code_spans() {
+        assert_eq!(
+            c_doc_of("Returns `sum()` of `KEY_LEN` values."),
+            "Returns `geo_sum()` of `GEO_KEY_LEN` values."
+        );
+        assert_eq!(
+            c_doc_of("Same as `Polyline` and `Corner::TopLeft`."),
+            "Same as `GeoPolyline` and `GEO_CORNER_TOP_LEFT`."
+        );
+        assert_eq!(
+            c_doc_of("Unlike `x + 1` or `len()`."),
+            "Unlike `x + 1` or `len()`."
+        );
+        assert_eq!(c_doc_of("An unclosed ` stays."), "An unclosed ` stays.");
+    }
+
+    #[test]
+    fn translates_links() {
+        assert_eq!(
+            c_doc_of("See [Polyline::push]."),
+            "See geo_polyline_push()."
+        );
+        assert_eq!(c_doc_of("See [`Self::push`]."), "See geo_polyline_push().");
+        assert_eq!(
+            c_doc_of("See [`sum()`] and [fn@sum]."),
+            "See geo_sum() and geo_sum()."
+        );
+        assert_eq!(c_doc_of("Up to [crate::KEY_LEN]."), "Up to GEO_KEY_LEN.");
+        assert_eq!(c_doc_of("See [shapes::sum]."), "See geo_sum().");
+        assert_eq!(
+            c_doc_of("From [Corner::TopLeft]."),
+            "From GEO_CORNER_TOP_LEFT."
+        );
+        assert_eq!(
+            c_doc_of("Returns [the sum](sum)."),
+            "Returns the sum (geo_sum())."
+        );
+        assert_eq!(c_doc_of("A [line][Polyline]."), "A line (GeoPolyline).");
+        assert_eq!(
+            c_doc_of("Calls [`push`](Polyline::push)."),
+            "Calls `push` (geo_polyline_push())."
+        );
+    }
+
+    #[test]
+    fn leaves_other_brackets() {
+        assert_eq!(c_doc_of("In [0, `KEY_LEN`)."), "In [0, `GEO_KEY_LEN`).");
+        assert_eq!(c_doc_of("See [Polyline::len]."), "See [Polyline::len].");
+        assert_eq!(c_doc_of("See [Other]."), "See [Other].");
+        assert_eq!(c_doc_of("An unclosed [ stays."), "An unclosed [ stays.");
+    }
+
+    #[test]
+    fn drops_rust_code_blocks() {
+        assert_eq!(
+            c_doc_of("Sums.\n\n```\nlet x = sum();\n```\n\n```rust\nsum();\n```\n\nFast."),
+            "Sums.\n\nFast."
+        );
+        assert_eq!(
+            c_doc_of("Sums.\n\n```c\ndouble x = `sum()`;\n```\n\n```text\n[sum]\n```"),
+            "Sums.\n\n```c\ndouble x = `sum()`;\n```\n\n```text\n[sum]\n```"
+        );
+        assert_eq!(c_doc_of("Sums.\n\n```\nsum();\n```"), "Sums.");
+    }
+
+    #[test]
+    fn splits_arguments() {
+        let doc = "Adds.\n\n# Arguments\n\n* `a` - The first\n  number.\n- `b`: The second.\n\n\
+                   # Returns\n\nThe sum.";
+        assert_eq!(
+            split_arguments(doc),
+            (
+                "Adds.\n\n# Returns\n\nThe sum.".to_owned(),
+                vec![
+                    ("a".to_owned(), "The first number.".to_owned()),
+                    ("b".to_owned(), "The second.".to_owned()),
+                ]
+            )
+        );
+        assert_eq!(
+            split_arguments("## arguments\n* `a` a"),
+            (String::new(), vec![("a".to_owned(), "a".to_owned())])
+        );
+    }
+
+    #[test]
+    fn ignores_arguments_in_code_blocks() {
+        let doc = "Adds.\n\n```text\n# Arguments\n* `a` - no\n```";
+        assert_eq!(split_arguments(doc), (doc.to_owned(), Vec::new()));
+        assert_eq!(
+            split_arguments("Adds.\n\n# Examples\n\n* `a` - no"),
+            ("Adds.\n\n# Examples\n\n* `a` - no".to_owned(), Vec::new())
+        );
+    }
+}
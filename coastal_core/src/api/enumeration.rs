@@ -0,0 +1,303 @@
+use convert_case::{Case, Casing};
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use quote::quote;
+use serde::{Deserialize, Serialize};
+use syn::{parse_str, Error, Index, Member, Type};
+
+use crate::{format_err, types::c_identifier};
+
+use super::{
+    doc::{c_doc, doxygen},
+    Library, State,
+};
+
+/// An enum passed to and from C as an integer, or as a tagged union if its variants have fields.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Enum {
+    pub name: String,
+    /// The integer type from `#[repr(...)]`, or `None` for a C `int`.
+    pub repr: Option<String>,
+    pub variants: Vec<Variant>,
+    /// The variant marked `#[default]`, if any.
+    pub default: Option<String>,
+    /// Overrides the library's policy for invalid values.
+    pub invalid: Option<InvalidEnumPolicy>,
+    /// Whether the variants are the error codes of `coastal::IntoCError`, from `#[coast(error)]`.
+    #[serde(default)]
+    pub error: bool,
+    /// The Markdown documentation from its doc comment.
+    #[serde(default)]
+    pub doc: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Variant {
+    pub name: String,
+    pub value: i64,
+    /// Named fields, or `0`, `1`, ... for tuple variants.
+    pub fields: Vec<(String, String)>,
+    /// The Markdown documentation from its doc comment.
+    #[serde(default)]
+    pub doc: Option<String>,
+}
+
+/// What to do when C passes a value that isn't one of an enum's variants.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InvalidEnumPolicy {
+    /// Print a message and abort the process, whatever the function's panic policy.
+    #[default]
+    Abort,
+    /// Return from the function immediately with its error value, as after a panic: `-1` for a
+    /// `Result`, null for a pointer, the function's `error_value` if it has one, or otherwise
+    /// zero or empty.
+    Error,
+    /// Use the variant marked `#[default]`.
+    Default,
+}
+
+impl Enum {
+    /// Returns the prefixed C name of the enum.
+    pub fn c_name(&self, lib: &Library) -> String {
+        format!("{}{}", lib.type_prefix, self.name)
+    }
+
+    /// Returns the prefixed C name of a variant, e.g. `MY_LIB_SHAPE_CIRCLE`.
+    pub fn c_variant_name(&self, lib: &Library, variant: &str) -> String {
+        format!(
+            "{}{}_{}",
+            lib.constant_prefix,
+            self.name.to_case(Case::ScreamingSnake),
+            variant.to_case(Case::ScreamingSnake)
+        )
+    }
+
+    /// Returns true if any variant has fields, so the enum is passed as a tagged union.
+    pub fn has_fields(&self) -> bool {
+        self.variants.iter().any(|v| !v.fields.is_empty())
+    }
+
+    /// Returns the integer type used for the enum, or for the tag of a tagged union.
+    pub fn raw_type(&self) -> TokenStream {
+        match &self.repr {
+            Some(repr) => parse_str(repr).expect("repr is an integer type"),
+            None => quote! { std::ffi::c_int },
+        }
+    }
+
+    /// Returns the policy for invalid values, checking that there's a default if it's needed.
+    pub fn invalid_policy(&self, lib: &Library) -> Result<InvalidEnumPolicy, Error> {
+        let policy = self.invalid.unwrap_or(lib.invalid_enum);
+        if policy == InvalidEnumPolicy::Default && self.default.is_none() {
+            return Err(format_err!(
+                "Coastal enum '{}' needs a #[default] variant to use it for invalid values",
+                self.name
+            ));
+        }
+        Ok(policy)
+    }
+
+    /// Returns the C definition of the enum.
+    ///
+    /// Enums with a `#[repr(...)]` integer type are a `typedef` of that type with anonymous enum
+    /// constants, because the size of a C `enum` can't be chosen in C11. Enums with fields are a
+    /// struct of the tag and a union named `data` of a struct for each variant with fields, which
+    /// isn't anonymous since that needs C11.
+    pub fn c_definition(&self, lib: &Library) -> Result<String, Error> {
+        self.invalid_policy(lib)?;
+        let c_name = self.c_name(lib);
+        let tag_name = if self.has_fields() {
+            format!("{c_name}Tag")
+        } else {
+            c_name.clone()
+        };
+        let mut constants = String::new();
+        for variant in &self.variants {
+            let doc = variant
+                .doc
+                .as_ref()
+                .map(|doc| c_doc(lib, doc, Some(&self.name)));
+            if let Some(comment) = doc.and_then(|doc| doxygen(&doc, "    ")) {
+                constants.push_str(&format!("{comment}\n"));
+            }
+            constants.push_str(&format!(
+                "    {} = {},\n",
+                self.c_variant_name(lib, &variant.name),
+                variant.value
+            ));
+        }
+        let mut definition = match &self.repr {
+            Some(repr) => {
+                let repr_type: Type = parse_str(repr)?;
+                let c_type = lib
+                    .c_value_type(&repr_type)
+                    .ok_or_else(|| format_err!("can't convert '{repr}' of '{}' to C", self.name))?;
+                format!("typedef {c_type} {tag_name};\nenum {{\n{constants}}};")
+            }
+            None => format!("typedef enum {tag_name} {{\n{constants}}} {tag_name};"),
+        };
+        let doc = self
+            .doc
+            .as_ref()
+            .map(|doc| c_doc(lib, doc, Some(&self.name)));
+        if let Some(comment) = doc.and_then(|doc| doxygen(&doc, "")) {
+            definition = format!("{comment}\n{definition}");
+        }
+        if !self.has_fields() {
+            return Ok(definition);
+        }
+        let mut members = String::new();
+        for variant in self.variants.iter().filter(|v| !v.fields.is_empty()) {
+            let struct_name = format!("{c_name}{}", variant.name);
+            definition.push_str(&format!("\n\ntypedef struct {struct_name} {{\n"));
+            for (name, ty) in &variant.fields {
+                let field_type: Type = parse_str(ty)?;
+                let c_type = lib.c_value_type(&field_type).ok_or_else(|| {
+                    format_err!(
+                        "can't convert field '{name}: {ty}' of '{}::{}' to C",
+                        self.name,
+                        variant.name
+                    )
+                })?;
+                definition.push_str(&format!("    {c_type} {};\n", c_field_name(name)));
+            }
+            definition.push_str(&format!("}} {struct_name};"));
+            members.push_str(&format!(
+                "        {struct_name} {};\n",
+                c_identifier(&variant.name.to_case(Case::Snake))
+            ));
+        }
+        definition.push_str(&format!(
+            "\n\ntypedef struct {c_name} {{\n    {tag_name} tag;\n    union {{\n{members}    }} data;\n}} {c_name};"
+        ));
+        Ok(definition)
+    }
+
+    /// Returns the `#[repr(C)]` Rust types matching the C definition of an enum with fields, and
+    /// their conversions to and from the enum.
+    ///
+    /// These go in a `coastal_types` module inside the wrappers, where `super::Name` is the enum.
+    pub fn rust_types(&self) -> TokenStream {
+        let name = Ident::new(&self.name, Span::call_site());
+        let data_name = Ident::new(&format!("{}Data", self.name), Span::call_site());
+        let raw_type = self.raw_type();
+        let mut structs = TokenStream::new();
+        let mut members = TokenStream::new();
+        let mut from_rust = TokenStream::new();
+        let mut to_rust = TokenStream::new();
+        for variant in &self.variants {
+            let variant_name = Ident::new(&variant.name, Span::call_site());
+            let value = int_literal(variant.value);
+            let rust_members: Vec<Member> = variant.fields.iter().map(|(n, _)| member(n)).collect();
+            let c_fields: Vec<Ident> = variant
+                .fields
+                .iter()
+                .map(|(n, _)| Ident::new(&c_field_name(n), Span::call_site()))
+                .collect();
+            if variant.fields.is_empty() {
+                from_rust.extend(quote! {
+                    super::#name::#variant_name {} => Self {
+                        tag: #value,
+                        data: unsafe { std::mem::zeroed() },
+                    },
+                });
+                to_rust.extend(quote! { #value => super::#name::#variant_name {}, });
+                continue;
+            }
+            let struct_name =
+                Ident::new(&format!("{}{}", self.name, variant.name), Span::call_site());
+            let member_name = Ident::new(
+                &c_identifier(&variant.name.to_case(Case::Snake)),
+                Span::call_site(),
+            );
+            let types = variant
+                .fields
+                .iter()
+                .map(|(_, ty)| parse_str::<Type>(ty).expect("field type"));
+            structs.extend(quote! {
+                #[repr(C)]
+                pub struct #struct_name {
+                    #(pub #c_fields: #types,)*
+                }
+            });
+            members.extend(quote! {
+                pub #member_name: std::mem::ManuallyDrop<#struct_name>,
+            });
+            from_rust.extend(quote! {
+                super::#name::#variant_name { #(#rust_members: #c_fields),* } => Self {
+                    tag: #value,
+                    data: #data_name {
+                        #member_name: std::mem::ManuallyDrop::new(#struct_name { #(#c_fields),* }),
+                    },
+                },
+            });
+            to_rust.extend(quote! {
+                #value => {
+                    let #struct_name { #(#c_fields),* } =
+                        std::mem::ManuallyDrop::into_inner(unsafe { self.data.#member_name });
+                    super::#name::#variant_name { #(#rust_members: #c_fields),* }
+                }
+            });
+        }
+        quote! {
+            #structs
+
+            #[repr(C)]
+            pub union #data_name {
+                #members
+            }
+
+            #[repr(C)]
+            pub struct #name {
+                pub tag: #raw_type,
+                pub data: #data_name,
+            }
+
+            impl #name {
+                pub fn from_rust(value: super::#name) -> Self {
+                    match value {
+                        #from_rust
+                    }
+                }
+
+                /// Returns the enum, or the tag if it isn't valid.
+                pub fn to_rust(self) -> Result<super::#name, #raw_type> {
+                    Ok(match self.tag {
+                        #to_rust
+                        tag => return Err(tag),
+                    })
+                }
+            }
+        }
+    }
+}
+
+impl State for Enum {
+    const TYPE_NAME: &'static str = "coastal.enum";
+}
+
+/// Returns an integer literal for an enum value, which may be negative.
+pub(crate) fn int_literal(value: i64) -> TokenStream {
+    let magnitude = Literal::u64_unsuffixed(value.unsigned_abs());
+    if value < 0 {
+        quote! { -#magnitude }
+    } else {
+        quote! { #magnitude }
+    }
+}
+
+/// Returns the C name of a field, which is `_0`, `_1`, ... for tuple variants.
+fn c_field_name(name: &str) -> String {
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{name}")
+    } else {
+        c_identifier(name)
+    }
+}
+
+/// Returns the Rust name of a field, which is an index for tuple variants.
+fn member(name: &str) -> Member {
+    match name.parse::<u32>() {
+        Ok(index) => Member::Unnamed(Index::from(index as usize)),
+        Err(_) => Member::Named(Ident::new(name, Span::call_site())),
+    }
+}
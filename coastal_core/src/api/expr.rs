@@ -0,0 +1,647 @@
+use proc_macro2::Span;
+use serde::{Deserialize, Serialize};
+use syn::Error;
+
+use crate::format_err;
+
+use super::{constant::c_type_name, ConstantValue, Library};
+
+/// References to other constants deeper than this are assumed to be cyclic.
+const MAX_DEPTH: usize = 64;
+
+/// A constant expression, such as `1 << 16` or `MAX as usize * 2`.
+///
+/// Integer and float literals take their type from the context they're used in, as in Rust.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ConstExpr {
+    Int(u128),
+    Float(f64),
+    Bool(bool),
+    /// A reference to another `#[coast]` constant by its Rust name.
+    Const(String),
+    Unary(UnaryOp, Box<ConstExpr>),
+    Binary(BinaryOp, Box<ConstExpr>, Box<ConstExpr>),
+    /// `expr as ty`, where `ty` is a primitive type name such as `u32`.
+    Cast(Box<ConstExpr>, String),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    fn c_operator(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::BitAnd => "&",
+            BinaryOp::BitOr => "|",
+            BinaryOp::BitXor => "^",
+            BinaryOp::Shl => "<<",
+            BinaryOp::Shr => ">>",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+        }
+    }
+}
+
+/// The result of evaluating a constant expression.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Number {
+    Int(i128),
+    Float(f64),
+    Bool(bool),
+}
+
+impl ConstExpr {
+    /// Evaluates the expression as the type `ty`, following references through `lib`.
+    pub fn evaluate(&self, ty: &str, lib: &Library) -> Result<Number, Error> {
+        self.eval(ty, lib, 0)
+    }
+
+    /// Returns the Rust names of the constants the expression refers to.
+    pub fn references(&self) -> Vec<&str> {
+        match self {
+            ConstExpr::Int(_) | ConstExpr::Float(_) | ConstExpr::Bool(_) => Vec::new(),
+            ConstExpr::Const(name) => vec![name],
+            ConstExpr::Unary(_, expr) | ConstExpr::Cast(expr, _) => expr.references(),
+            ConstExpr::Binary(_, left, right) => {
+                let mut names = left.references();
+                names.extend(right.references());
+                names
+            }
+        }
+    }
+
+    /// Returns a C expression of type `ty`, referring to other constants by their C names.
+    pub fn c_expr(&self, ty: &str, lib: &Library) -> Result<String, Error> {
+        if let Some(value) = self.literal(ty) {
+            return Ok(ConstantValue::from_number(ty, check_range(value, ty)?)?.c_literal());
+        }
+        match self {
+            ConstExpr::Int(_) | ConstExpr::Float(_) | ConstExpr::Bool(_) => unreachable!(),
+            ConstExpr::Const(name) => Ok(lib.constant(name)?.c_name(lib)),
+            ConstExpr::Unary(UnaryOp::Neg, expr) => Ok(format!("(-{})", expr.c_expr(ty, lib)?)),
+            ConstExpr::Unary(UnaryOp::Not, expr) if ty == "bool" => {
+                Ok(format!("(!{})", expr.c_expr(ty, lib)?))
+            }
+            // C promotes small integers to int before complementing, so cast back.
+            ConstExpr::Unary(UnaryOp::Not, expr) => {
+                Ok(format!("(({})~{})", c_type(ty)?, expr.c_expr(ty, lib)?))
+            }
+            ConstExpr::Binary(op @ (BinaryOp::Shl | BinaryOp::Shr), left, right) => {
+                let right_ty = right.natural_type(lib, 0)?;
+                Ok(format!(
+                    "({} {} {})",
+                    left.c_expr(ty, lib)?,
+                    op.c_operator(),
+                    right.c_expr(&right_ty, lib)?
+                ))
+            }
+            // C's `%` only takes integers, so write the value of a float remainder instead.
+            ConstExpr::Binary(BinaryOp::Rem, ..) if matches!(ty, "f32" | "f64") => {
+                let value = self.evaluate(ty, lib)?;
+                Ok(ConstantValue::from_number(ty, value)?.c_literal())
+            }
+            ConstExpr::Binary(op, left, right) => Ok(format!(
+                "({} {} {})",
+                left.c_expr(ty, lib)?,
+                op.c_operator(),
+                right.c_expr(ty, lib)?
+            )),
+            ConstExpr::Cast(expr, cast_ty) => {
+                let inner_ty = expr.natural_type(lib, 0)?;
+                Ok(format!(
+                    "(({}){})",
+                    c_type(cast_ty)?,
+                    expr.c_expr(&inner_ty, lib)?
+                ))
+            }
+        }
+    }
+
+    fn eval(&self, ty: &str, lib: &Library, depth: usize) -> Result<Number, Error> {
+        if depth > MAX_DEPTH {
+            return Err(format_err!(
+                "Coastal constants refer to each other in a cycle"
+            ));
+        }
+        if let Some(value) = self.literal(ty) {
+            return check_range(value, ty);
+        }
+        let value = match self {
+            ConstExpr::Int(_) | ConstExpr::Float(_) | ConstExpr::Bool(_) => unreachable!(),
+            ConstExpr::Const(name) => {
+                let constant = lib.constant(name)?;
+                match &constant.value {
+                    ConstantValue::Expr(ty, expr) => expr.eval(ty, lib, depth + 1)?,
+                    value => value.number().ok_or_else(|| {
+                        format_err!("Coastal constant '{name}' can't be used in an expression")
+                    })?,
+                }
+            }
+            ConstExpr::Unary(op, expr) => match (op, expr.eval(ty, lib, depth)?) {
+                (UnaryOp::Neg, Number::Int(val)) => {
+                    Number::Int(val.checked_neg().ok_or_else(|| overflow(ty))?)
+                }
+                (UnaryOp::Neg, Number::Float(val)) => Number::Float(-val),
+                (UnaryOp::Not, Number::Int(val)) => Number::Int(wrap(!val, ty)?),
+                (UnaryOp::Not, Number::Bool(val)) => Number::Bool(!val),
+                _ => return Err(invalid_operand(ty)),
+            },
+            ConstExpr::Binary(op @ (BinaryOp::Shl | BinaryOp::Shr), left, right) => {
+                let right_ty = right.natural_type(lib, depth)?;
+                let (Number::Int(val), Number::Int(shift)) = (
+                    left.eval(ty, lib, depth)?,
+                    right.eval(&right_ty, lib, depth)?,
+                ) else {
+                    return Err(invalid_operand(ty));
+                };
+                let (bits, _) = int_type(ty).ok_or_else(|| invalid_operand(ty))?;
+                let shift = u32::try_from(shift)
+                    .ok()
+                    .filter(|shift| *shift < bits)
+                    .ok_or_else(|| overflow(ty))?;
+                match op {
+                    BinaryOp::Shl => Number::Int(wrap(val << shift, ty)?),
+                    _ => Number::Int(val >> shift),
+                }
+            }
+            ConstExpr::Binary(op, left, right) => {
+                match (op, left.eval(ty, lib, depth)?, right.eval(ty, lib, depth)?) {
+                    (_, Number::Int(a), Number::Int(b)) => Number::Int(
+                        match op {
+                            BinaryOp::Add => a.checked_add(b),
+                            BinaryOp::Sub => a.checked_sub(b),
+                            BinaryOp::Mul => a.checked_mul(b),
+                            BinaryOp::Div => a.checked_div(b),
+                            BinaryOp::Rem => a.checked_rem(b),
+                            BinaryOp::BitAnd => Some(a & b),
+                            BinaryOp::BitOr => Some(a | b),
+                            BinaryOp::BitXor => Some(a ^ b),
+                            _ => return Err(invalid_operand(ty)),
+                        }
+                        .ok_or_else(|| overflow(ty))?,
+                    ),
+                    (_, Number::Float(a), Number::Float(b)) => Number::Float(match op {
+                        BinaryOp::Add => a + b,
+                        BinaryOp::Sub => a - b,
+                        BinaryOp::Mul => a * b,
+                        BinaryOp::Div => a / b,
+                        BinaryOp::Rem => a % b,
+                        _ => return Err(invalid_operand(ty)),
+                    }),
+                    (BinaryOp::And, Number::Bool(a), Number::Bool(b)) => Number::Bool(a && b),
+                    (BinaryOp::Or, Number::Bool(a), Number::Bool(b)) => Number::Bool(a || b),
+                    (BinaryOp::BitAnd, Number::Bool(a), Number::Bool(b)) => Number::Bool(a & b),
+                    (BinaryOp::BitOr, Number::Bool(a), Number::Bool(b)) => Number::Bool(a | b),
+                    (BinaryOp::BitXor, Number::Bool(a), Number::Bool(b)) => Number::Bool(a ^ b),
+                    _ => return Err(invalid_operand(ty)),
+                }
+            }
+            ConstExpr::Cast(expr, cast_ty) => {
+                let inner_ty = expr.natural_type(lib, depth)?;
+                cast(expr.eval(&inner_ty, lib, depth)?, cast_ty)?
+            }
+        };
+        check_range(value, ty)
+    }
+
+    /// Returns the value of a literal, including negated literals so that `-128` fits in an `i8`.
+    fn literal(&self, ty: &str) -> Option<Number> {
+        let int = |val: u128, negative: bool| match ty {
+            "f32" | "f64" => Some(Number::Float(if negative {
+                -(val as f64)
+            } else {
+                val as f64
+            })),
+            _ if negative => 0i128.checked_sub_unsigned(val).map(Number::Int),
+            // Too large for an i128, so leave it to fail the range check.
+            _ => Some(Number::Int(i128::try_from(val).unwrap_or(i128::MAX))),
+        };
+        match self {
+            ConstExpr::Int(val) => int(*val, false),
+            ConstExpr::Float(val) => Some(Number::Float(*val)),
+            ConstExpr::Bool(val) => Some(Number::Bool(*val)),
+            ConstExpr::Unary(UnaryOp::Neg, expr) => match expr.as_ref() {
+                ConstExpr::Int(val) => int(*val, true),
+                ConstExpr::Float(val) => Some(Number::Float(-val)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the type the expression has without any context, defaulting integer literals to
+    /// `i32` and float literals to `f64` as Rust does.
+    fn natural_type(&self, lib: &Library, depth: usize) -> Result<String, Error> {
+        if depth > MAX_DEPTH {
+            return Err(format_err!(
+                "Coastal constants refer to each other in a cycle"
+            ));
+        }
+        Ok(match self {
+            ConstExpr::Int(_) => "i32".to_owned(),
+            ConstExpr::Float(_) => "f64".to_owned(),
+            ConstExpr::Bool(_) => "bool".to_owned(),
+            ConstExpr::Const(name) => lib.constant(name)?.value.rust_type().to_owned(),
+            ConstExpr::Unary(_, expr) => expr.natural_type(lib, depth + 1)?,
+            ConstExpr::Binary(BinaryOp::Shl | BinaryOp::Shr, left, _) => {
+                left.natural_type(lib, depth + 1)?
+            }
+            ConstExpr::Binary(_, left, right) => {
+                if left.is_literal() {
+                    right.natural_type(lib, depth + 1)?
+                } else {
+                    left.natural_type(lib, depth + 1)?
+                }
+            }
+            ConstExpr::Cast(_, ty) => ty.clone(),
+        })
+    }
+
+    fn is_literal(&self) -> bool {
+        match self {
+            ConstExpr::Int(_) | ConstExpr::Float(_) => true,
+            ConstExpr::Unary(_, expr) => expr.is_literal(),
+            ConstExpr::Binary(_, left, right) => left.is_literal() && right.is_literal(),
+            _ => false,
+        }
+    }
+}
+
+/// Returns the width and signedness of a Rust integer type.
+pub(crate) fn int_type(ty: &str) -> Option<(u32, bool)> {
+    Some(match ty {
+        "i8" => (8, true),
+        "i16" => (16, true),
+        "i32" => (32, true),
+        "i64" | "isize" => (64, true),
+        "i128" => (128, true),
+        "u8" => (8, false),
+        "u16" => (16, false),
+        "u32" => (32, false),
+        "u64" | "usize" => (64, false),
+        "u128" => (128, false),
+        _ => return None,
+    })
+}
+
+fn c_type(ty: &str) -> Result<&'static str, Error> {
+    c_type_name(ty).ok_or_else(|| format_err!("Coastal can't cast a constant to '{ty}'"))
+}
+
+/// Truncates an integer to the width of `ty`, as `as` does.
+fn wrap(val: i128, ty: &str) -> Result<i128, Error> {
+    let (bits, signed) = int_type(ty).ok_or_else(|| invalid_operand(ty))?;
+    if bits == 128 {
+        return Ok(val);
+    }
+    let truncated = val & ((1 << bits) - 1);
+    if signed && truncated >= 1 << (bits - 1) {
+        Ok(truncated - (1 << bits))
+    } else {
+        Ok(truncated)
+    }
+}
+
+fn cast(value: Number, ty: &str) -> Result<Number, Error> {
+    Ok(match (value, ty) {
+        (Number::Int(val), "f32" | "f64") => Number::Float(val as f64),
+        (Number::Float(val), "f32" | "f64") => Number::Float(val),
+        (Number::Int(val), _) => Number::Int(wrap(val, ty)?),
+        (Number::Bool(val), _) => Number::Int(wrap(val.into(), ty)?),
+        (Number::Float(val), _) => {
+            // Float to integer casts saturate, with NaN becoming zero.
+            let (min, max) = int_range(ty).ok_or_else(|| invalid_operand(ty))?;
+            Number::Int(if val.is_nan() {
+                0
+            } else {
+                (val as i128).clamp(min, max)
+            })
+        }
+    })
+}
+
+fn check_range(value: Number, ty: &str) -> Result<Number, Error> {
+    match value {
+        Number::Int(val) => {
+            let (min, max) = int_range(ty).ok_or_else(|| invalid_operand(ty))?;
+            if val < min || val > max {
+                return Err(overflow(ty));
+            }
+        }
+        // C has no literals for infinity and NaN.
+        Number::Float(val) if !val.is_finite() => return Err(not_finite(ty)),
+        Number::Float(val) if ty == "f32" => {
+            if !(val as f32).is_finite() {
+                return Err(overflow(ty));
+            }
+            return Ok(Number::Float(f64::from(val as f32)));
+        }
+        Number::Float(_) if ty == "f64" => (),
+        Number::Bool(_) if ty == "bool" => (),
+        _ => return Err(invalid_operand(ty)),
+    }
+    Ok(value)
+}
+
+/// Returns the range of an integer type, limited to what fits in an `i128`.
+fn int_range(ty: &str) -> Option<(i128, i128)> {
+    let (bits, signed) = int_type(ty)?;
+    Some(match (bits, signed) {
+        (128, true) => (i128::MIN, i128::MAX),
+        (128, false) => (0, i128::MAX),
+        (bits, true) => (-(1 << (bits - 1)), (1 << (bits - 1)) - 1),
+        (bits, false) => (0, (1 << bits) - 1),
+    })
+}
+
+fn overflow(ty: &str) -> Error {
+    format_err!("Coastal constant expression overflows '{ty}'")
+}
+
+fn not_finite(ty: &str) -> Error {
+    format_err!("Coastal constant expression is infinite or NaN as '{ty}'")
+}
+
+fn invalid_operand(ty: &str) -> Error {
+    format_err!("Coastal can't evaluate this constant expression as '{ty}'")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Constant;
+
+    fn lib() -> Library {
+        let mut lib = Library::new();
+        lib.set_prefix("test");
+        for (name, value) in [
+            ("MAX", ConstantValue::U32(100)),
+            ("SCALE", ConstantValue::F64(0.5)),
+            ("LOWEST", ConstantValue::I128(i128::MIN)),
+            (
+                "SIZE",
+                ConstantValue::Expr("u32".to_owned(), mul(constant("MAX"), int(2))),
+            ),
+            (
+                "LOOP",
+                ConstantValue::Expr("u32".to_owned(), constant("LOOP")),
+            ),
+        ] {
+            lib.constants.push(Constant {
+                name: name.to_owned(),
+                value,
+                options: Default::default(),
+            });
+        }
+        lib
+    }
+
+    fn int(val: u128) -> ConstExpr {
+        ConstExpr::Int(val)
+    }
+
+    fn float(val: f64) -> ConstExpr {
+        ConstExpr::Float(val)
+    }
+
+    fn constant(name: &str) -> ConstExpr {
+        ConstExpr::Const(name.to_owned())
+    }
+
+    fn neg(expr: ConstExpr) -> ConstExpr {
+        ConstExpr::Unary(UnaryOp::Neg, Box::new(expr))
+    }
+
+    fn not(expr: ConstExpr) -> ConstExpr {
+        ConstExpr::Unary(UnaryOp::Not, Box::new(expr))
+    }
+
+    fn binary(op: BinaryOp, left: ConstExpr, right: ConstExpr) -> ConstExpr {
+        ConstExpr::Binary(op, Box::new(left), Box::new(right))
+    }
+
+    fn mul(left: ConstExpr, right: ConstExpr) -> ConstExpr {
+        binary(BinaryOp::Mul, left, right)
+    }
+
+    fn cast(expr: ConstExpr, ty: &str) -> ConstExpr {
+        ConstExpr::Cast(Box::new(expr), ty.to_owned())
+    }
+
+    fn eval(expr: ConstExpr, ty: &str) -> Result<Number, String> {
+        expr.evaluate(ty, &lib()).map_err(|err| err.to_string())
+    }
+
+    fn c_expr(expr: ConstExpr, ty: &str) -> String {
+        expr.c_expr(ty, &lib()).unwrap()
+    }
+
+    #[test]
+    fn evaluates_integers() {
+        assert_eq!(
+            eval(binary(BinaryOp::Add, int(1), mul(int(2), int(3))), "u32"),
+            Ok(Number::Int(7))
+        );
+        assert_eq!(
+            eval(binary(BinaryOp::Rem, int(7), int(4)), "i32"),
+            Ok(Number::Int(3))
+        );
+        assert_eq!(
+            eval(binary(BinaryOp::BitOr, int(0xF0), int(0x0F)), "u8"),
+            Ok(Number::Int(0xFF))
+        );
+        assert_eq!(eval(not(int(0)), "u8"), Ok(Number::Int(0xFF)));
+        assert_eq!(eval(not(int(0)), "i8"), Ok(Number::Int(-1)));
+        assert_eq!(eval(neg(int(128)), "i8"), Ok(Number::Int(-128)));
+        assert_eq!(
+            eval(binary(BinaryOp::Shl, int(1), int(7)), "u8"),
+            Ok(Number::Int(128))
+        );
+        assert_eq!(
+            eval(binary(BinaryOp::Shr, neg(int(8)), int(1)), "i32"),
+            Ok(Number::Int(-4))
+        );
+    }
+
+    #[test]
+    fn evaluates_references() {
+        assert_eq!(eval(constant("SIZE"), "u32"), Ok(Number::Int(200)));
+        assert_eq!(
+            eval(binary(BinaryOp::Sub, constant("MAX"), int(1)), "u32"),
+            Ok(Number::Int(99))
+        );
+        assert_eq!(
+            eval(constant("LOOP"), "u32"),
+            Err("Coastal constants refer to each other in a cycle".to_owned())
+        );
+        assert_eq!(
+            eval(constant("MISSING"), "u32"),
+            Err("Coastal constant 'MISSING' must also be listed in api!".to_owned())
+        );
+    }
+
+    #[test]
+    fn rejects_overflow() {
+        let overflow = |ty: &str| Err(format!("Coastal constant expression overflows '{ty}'"));
+        assert_eq!(
+            eval(binary(BinaryOp::Add, int(200), int(100)), "u8"),
+            overflow("u8")
+        );
+        assert_eq!(eval(int(128), "i8"), overflow("i8"));
+        assert_eq!(eval(neg(int(1)), "u32"), overflow("u32"));
+        assert_eq!(
+            eval(binary(BinaryOp::Shl, int(1), int(8)), "u8"),
+            overflow("u8")
+        );
+        assert_eq!(
+            eval(binary(BinaryOp::Div, int(1), int(0)), "i32"),
+            overflow("i32")
+        );
+        assert_eq!(eval(float(1e39), "f32"), overflow("f32"));
+        assert_eq!(eval(neg(constant("LOWEST")), "i128"), overflow("i128"));
+        assert_eq!(
+            eval(
+                neg(binary(BinaryOp::Sub, constant("LOWEST"), int(0))),
+                "i128"
+            ),
+            overflow("i128")
+        );
+        assert_eq!(
+            eval(
+                neg(binary(BinaryOp::Add, constant("LOWEST"), int(1))),
+                "i128"
+            ),
+            Ok(Number::Int(i128::MAX))
+        );
+    }
+
+    #[test]
+    fn evaluates_casts() {
+        assert_eq!(eval(cast(neg(int(1)), "u8"), "u8"), Ok(Number::Int(0xFF)));
+        assert_eq!(eval(cast(int(300), "u8"), "u8"), Ok(Number::Int(44)));
+        assert_eq!(eval(cast(float(300.5), "u8"), "u8"), Ok(Number::Int(0xFF)));
+        assert_eq!(eval(cast(float(-1.5), "i32"), "i32"), Ok(Number::Int(-1)));
+        assert_eq!(eval(cast(int(3), "f64"), "f64"), Ok(Number::Float(3.0)));
+        assert_eq!(
+            eval(cast(constant("MAX"), "u8"), "u8"),
+            Ok(Number::Int(100))
+        );
+    }
+
+    #[test]
+    fn evaluates_floats() {
+        assert_eq!(
+            eval(mul(constant("SCALE"), int(3)), "f64"),
+            Ok(Number::Float(1.5))
+        );
+        assert_eq!(
+            eval(binary(BinaryOp::Rem, float(7.5), float(2.0)), "f64"),
+            Ok(Number::Float(1.5))
+        );
+        assert_eq!(
+            eval(float(0.1), "f32"),
+            Ok(Number::Float(f64::from(0.1f32)))
+        );
+        let not_finite = Err("Coastal constant expression is infinite or NaN as 'f64'".to_owned());
+        assert_eq!(
+            eval(binary(BinaryOp::Div, float(1.0), float(0.0)), "f64"),
+            not_finite
+        );
+        assert_eq!(
+            eval(binary(BinaryOp::Div, float(0.0), float(0.0)), "f64"),
+            not_finite
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_operands() {
+        let invalid = |ty: &str| {
+            Err(format!(
+                "Coastal can't evaluate this constant expression as '{ty}'"
+            ))
+        };
+        assert_eq!(eval(float(1.0), "u32"), invalid("u32"));
+        assert_eq!(eval(not(float(1.0)), "f64"), invalid("f64"));
+        assert_eq!(
+            eval(binary(BinaryOp::BitAnd, float(1.0), float(2.0)), "f64"),
+            invalid("f64")
+        );
+        assert_eq!(
+            eval(binary(BinaryOp::And, int(1), int(2)), "i32"),
+            invalid("i32")
+        );
+    }
+
+    #[test]
+    fn writes_c_expressions() {
+        assert_eq!(c_expr(int(5), "u32"), "UINT32_C(5)");
+        assert_eq!(c_expr(neg(int(128)), "i8"), "((int8_t)-128)");
+        assert_eq!(c_expr(constant("MAX"), "u32"), "TEST_MAX");
+        assert_eq!(
+            c_expr(mul(constant("MAX"), int(2)), "u32"),
+            "(TEST_MAX * UINT32_C(2))"
+        );
+        assert_eq!(c_expr(not(int(0)), "u8"), "((uint8_t)~((uint8_t)0u))");
+        assert_eq!(c_expr(not(ConstExpr::Bool(false)), "bool"), "(!false)");
+        assert_eq!(
+            c_expr(binary(BinaryOp::Shl, int(1), int(4)), "u64"),
+            "(UINT64_C(1) << INT32_C(4))"
+        );
+        assert_eq!(
+            c_expr(cast(neg(int(1)), "u8"), "u8"),
+            "((uint8_t)INT32_C(-1))"
+        );
+    }
+
+    #[test]
+    fn writes_float_remainders_as_values() {
+        assert_eq!(
+            c_expr(binary(BinaryOp::Rem, float(7.5), float(2.0)), "f64"),
+            "1.5"
+        );
+        assert_eq!(
+            c_expr(
+                binary(BinaryOp::Rem, constant("SCALE"), float(0.375)),
+                "f32"
+            ),
+            "0.125f"
+        );
+        assert_eq!(
+            c_expr(binary(BinaryOp::Rem, int(7), int(4)), "i32"),
+            "(INT32_C(7) % INT32_C(4))"
+        );
+    }
+
+    #[test]
+    fn rejects_non_finite_literals() {
+        let err = float(f64::INFINITY).c_expr("f64", &lib()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Coastal constant expression is infinite or NaN as 'f64'"
+        );
+    }
+}
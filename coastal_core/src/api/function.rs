@@ -1,62 +1,511 @@
+use std::str::FromStr;
+
+use convert_case::{Case, Casing};
 use proc_macro2::{Ident, Span, TokenStream};
-use quote::quote;
+use quote::{quote, ToTokens};
 use serde::{Deserialize, Serialize};
-use syn::Type;
-use syn::{parse_str, Error};
+use syn::{parse_str, Error, Expr, ExprUnary, UnOp};
+use syn::{ReturnType, Type};
 
-use super::{Library, State};
+use super::{
+    doc::{c_doc, doxygen},
+    Library, SliceType, State,
+};
+use crate::{format_err, types::replace_idents};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Function {
     pub name: String,
     pub return_type: String,
+    pub arguments: Vec<Argument>,
+    /// The type for associated functions and methods, whose receiver is the first argument.
+    #[serde(default)]
+    pub self_type: Option<String>,
+    /// Overrides the library's policy for panics.
+    #[serde(default)]
+    pub panic: Option<PanicPolicy>,
+    /// The Rust expression returned to C after a panic, instead of zero.
+    #[serde(default)]
+    pub error_value: Option<String>,
+    /// Whether the function is an `unsafe fn`, which the wrapper calls in an `unsafe` block.
+    #[serde(default)]
+    pub is_unsafe: bool,
+    #[serde(default)]
+    pub return_options: ReturnOptions,
+    #[serde(default)]
+    pub options: ItemOptions,
+    /// The instantiations of a generic function, from `#[coast(instantiate(...))]`, which are
+    /// each exported as a function.
+    #[serde(default)]
+    pub instantiations: Vec<Instantiation>,
+    /// Which instantiation of a generic function this is.
+    #[serde(default)]
+    pub instantiation: Option<Instantiation>,
+}
+
+/// The concrete generic arguments of one instantiation of a generic function.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Instantiation {
+    /// Each generic parameter and its argument, in the order they're declared.
     pub arguments: Vec<(String, String)>,
+    /// Added to the C name of the function, such as `_f32`.
+    pub suffix: String,
+}
+
+impl Instantiation {
+    /// Returns a type with the generic parameters replaced by their arguments.
+    fn substitute(&self, ty: &str) -> Result<String, Error> {
+        let arguments = self
+            .arguments
+            .iter()
+            .map(|(param, arg)| Ok((param.as_str(), TokenStream::from_str(arg)?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let tokens = replace_idents(TokenStream::from_str(ty)?, &|ident| {
+            arguments
+                .iter()
+                .find(|(param, _)| ident == param)
+                .map(|(_, arg)| arg.clone())
+        });
+        Ok(tokens.to_string())
+    }
+}
+
+/// An argument of an exported function.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Argument {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+    #[serde(default)]
+    pub options: ArgOptions,
+    /// The Markdown description from its doc comment, or the function's `# Arguments` section.
+    #[serde(default)]
+    pub doc: Option<String>,
+}
+
+/// Options from `#[coast(...)]` on an argument.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ArgOptions {
+    /// `len`: pass a string as a pointer and a length, rather than NUL-terminated.
+    #[serde(default)]
+    pub len: bool,
+    /// `invalid_utf8 = "..."`: overrides the library's policy for invalid UTF-8.
+    #[serde(default)]
+    pub invalid_utf8: Option<Utf8Policy>,
+    /// `nullable`: pass an `Option` of a value as a pointer, rather than a flag and the value.
+    #[serde(default)]
+    pub nullable: bool,
+    /// `c_type = "..."`: overrides the C type of the parameter in the header.
+    #[serde(default)]
+    pub c_type: Option<String>,
+    /// The C name of the function the argument belongs to, which its callback type is named
+    /// after. This is set when converting, rather than by `#[coast(...)]`.
+    #[serde(skip)]
+    pub function: String,
+}
+
+/// Options from `#[coast(...)]` on a function that affect how it returns.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReturnOptions {
+    /// `out = "a, b"`: the names of the out-parameters for the elements of a returned tuple,
+    /// rather than `out_0`, `out_1`, ...
+    #[serde(default)]
+    pub out: Vec<String>,
+    /// `c_type = "..."`: overrides the C return type in the header.
+    #[serde(default)]
+    pub c_type: Option<String>,
+    /// The names of the function's arguments, which out-parameters mustn't clash with. This is
+    /// set when converting, rather than by `#[coast(...)]`.
+    #[serde(skip)]
+    pub arguments: Vec<String>,
+}
+
+impl ReturnOptions {
+    /// Returns the name for an out-parameter, with underscores added until it doesn't clash with
+    /// an argument, e.g. `out_` for `fn f(out: i32) -> Result<i32, E>`.
+    pub fn out_name(&self, name: &str) -> Ident {
+        let mut name = name.to_owned();
+        while self.arguments.contains(&name) {
+            name.push('_');
+        }
+        Ident::new(&name, Span::call_site())
+    }
+}
+
+/// Options from `#[coast(...)]` shared by exported functions and constants.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ItemOptions {
+    /// `name = "..."`: the exported C name, instead of the prefixed Rust name.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// `skip`: leave the item out of the library, even if `api!` lists it.
+    #[serde(default)]
+    pub skip: bool,
+    /// `deprecated` or `deprecated = "..."`: the item is deprecated, with an optional note.
+    #[serde(default)]
+    pub deprecated: Option<String>,
+    /// `since = "..."`: the version of the library that added the item.
+    #[serde(default)]
+    pub since: Option<String>,
+    /// The Markdown documentation of the item, from its doc comment or `doc = "..."`.
+    #[serde(default)]
+    pub doc: Option<String>,
+}
+
+impl ItemOptions {
+    /// Returns the Doxygen comment for the item, or `None` if there's nothing to say.
+    ///
+    /// `details`, such as how to free the return value, go after the documentation, followed by
+    /// `tags` such as `@param`. `self_type` is the type that `Self` refers to in the docs.
+    pub fn c_comment(
+        &self,
+        lib: &Library,
+        self_type: Option<&str>,
+        details: &str,
+        tags: &[String],
+    ) -> Option<String> {
+        let mut tags = tags.to_vec();
+        tags.extend(self.since.as_ref().map(|since| format!("@since {since}")));
+        tags.extend(self.deprecated.as_ref().map(|note| {
+            format!("@deprecated {}", c_doc(lib, note, self_type))
+                .trim_end()
+                .to_owned()
+        }));
+        let doc = self.doc.as_ref().map(|doc| c_doc(lib, doc, self_type));
+        let sections = [
+            doc.unwrap_or_default(),
+            details.trim().to_owned(),
+            tags.join("\n"),
+        ];
+        let sections: Vec<_> = sections.iter().filter(|s| !s.is_empty()).cloned().collect();
+        doxygen(&sections.join("\n\n"), "")
+    }
+
+    /// Returns the attributes that go before a function's prototype, such as
+    /// `MY_LIB_DEPRECATED("...") `.
+    pub fn c_attributes(&self, lib: &Library) -> String {
+        match &self.deprecated {
+            Some(note) => format!("{}({note:?}) ", lib.deprecated_macro()),
+            None => String::new(),
+        }
+    }
+}
+
+/// What to do when C passes a string argument that isn't valid UTF-8.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Utf8Policy {
+    /// Panic, which is then handled by the panic policy.
+    #[default]
+    Panic,
+    /// Return from the function immediately with its error value.
+    Error,
+    /// Replace invalid sequences with U+FFFD, which copies the string.
+    Lossy,
+}
+
+/// What to do when a function returns a string containing a NUL character, which C can't see past.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NulPolicy {
+    /// Panic, which is then handled by the panic policy.
+    #[default]
+    Panic,
+    /// Return the function's error value, which is null for strings.
+    Error,
+    /// Return the string up to the first NUL character.
+    Truncate,
+}
+
+/// What a wrapper does when the function it calls panics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PanicPolicy {
+    /// Print a message and abort the process.
+    #[default]
+    Abort,
+    /// Return the function's error value, which is `-1` for a `Result`, null for a pointer and
+    /// otherwise zero or empty by default. The header documents it for each function.
+    Error,
+    /// Return the error value, and keep the panic message for `prefix_last_error_message()`.
+    LastError,
 }
 
 impl Function {
+    /// Returns the name of the exported C function.
+    ///
+    /// An instantiation of a generic function adds its suffix, even to a `name = "..."`.
+    pub fn c_name(&self, lib: &Library) -> String {
+        let suffix = self.instantiation.as_ref().map_or("", |i| &i.suffix);
+        if let Some(name) = &self.options.name {
+            return format!("{name}{suffix}");
+        }
+        match &self.self_type {
+            Some(ty) => format!(
+                "{}{}_{}{suffix}",
+                lib.function_prefix,
+                ty.to_case(Case::Snake),
+                self.name
+            ),
+            None => format!("{}{}{suffix}", lib.function_prefix, self.name),
+        }
+    }
+
+    /// Returns the function, or a function for each instantiation of a generic function.
+    pub fn instantiate(&self) -> Result<Vec<Function>, Error> {
+        if self.instantiations.is_empty() {
+            return Ok(vec![self.clone()]);
+        }
+        self.instantiations
+            .iter()
+            .map(|instantiation| {
+                let arguments = self
+                    .arguments
+                    .iter()
+                    .map(|arg| {
+                        Ok(Argument {
+                            ty: instantiation.substitute(&arg.ty)?,
+                            ..arg.clone()
+                        })
+                    })
+                    .collect::<Result<_, Error>>()?;
+                Ok(Function {
+                    return_type: instantiation.substitute(&self.return_type)?,
+                    arguments,
+                    instantiations: Vec::new(),
+                    instantiation: Some(instantiation.clone()),
+                    ..self.clone()
+                })
+            })
+            .collect()
+    }
+
     pub fn rust_wrapper(&self, lib: &Library) -> Result<TokenStream, Error> {
         let name = Ident::new(&self.name, Span::call_site());
-        let wrapped_name = Ident::new(
-            &format!("{}{}", lib.function_prefix, self.name),
-            Span::call_site(),
-        );
+        let generics = match &self.instantiation {
+            Some(instantiation) => {
+                let arguments = instantiation
+                    .arguments
+                    .iter()
+                    .map(|(_, arg)| TokenStream::from_str(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                quote! { ::<#(#arguments),*> }
+            }
+            None => TokenStream::new(),
+        };
+        let path = match &self.self_type {
+            Some(ty) => {
+                let ty = Ident::new(ty, Span::call_site());
+                quote! { super::#ty::#name #generics }
+            }
+            None => quote! { super::#name #generics },
+        };
+        let wrapped_name = Ident::new(&self.c_name(lib), Span::call_site());
         let mut declarations = TokenStream::new();
         let mut call = TokenStream::new();
-        for (name, ty) in &self.arguments {
-            let n = Ident::new(name, Span::call_site());
-            let arg_type: Type = parse_str(ty)?;
-            let arg = lib.convert_arg(&n, &arg_type)?;
+        for arg in self.convert_arguments(lib)? {
             declarations.extend(arg.decl);
             call.extend(arg.call);
         }
-        let ConvertReturn {
-            before,
-            after,
-            return_type,
-            ..
-        } = lib.convert_return(&parse_str(&self.return_type)?)?;
+        let rust_return_type: ReturnType = parse_str(&self.return_type)?;
+        let ret = lib.convert_return(&rust_return_type, &self.return_options())?;
+        let (before, after, return_type) = (&ret.before, &ret.after, &ret.return_type);
+        declarations.extend(ret.decl.clone());
+        // The `unsafe` block is in parentheses, so that `after` can continue the expression.
+        let body = if self.is_unsafe {
+            quote! { #before (unsafe { #path(#call) }) #after }
+        } else {
+            quote! { #before #path(#call) #after }
+        };
+        // Functions that never return are left to abort if they panic.
+        if matches!(&rust_return_type, ReturnType::Type(_, ty) if matches!(**ty, Type::Never(_))) {
+            return Ok(quote! {
+                #[no_mangle]
+                #[allow(unused_unsafe, unused_parens)]
+                pub extern "C" fn #wrapped_name(#declarations) -> #return_type {
+                    #[allow(dead_code)]
+                    fn coastal_error() -> ! {
+                        std::process::abort()
+                    }
+                    #body
+                }
+            });
+        }
+        let (error_value, _) = self.error_value(&ret)?;
+        let policy = self.panic.unwrap_or(lib.panic);
+        let body = lib.catch_panic(&self.c_name(lib), policy, quote! { coastal_error() }, body);
         Ok(quote! {
             #[no_mangle]
+            // Conversions of arguments may be nested in the `unsafe` block for an `unsafe fn`.
+            #[allow(unused_unsafe, unused_parens)]
             pub extern "C" fn #wrapped_name(#declarations) -> #return_type {
-                #before super::#name(#call) #after
+                /// Returns the value for C when the call fails.
+                #[allow(dead_code)]
+                fn coastal_error() -> #return_type {
+                    #error_value
+                }
+                #body
             }
         })
     }
+
+    /// Returns the value returned to C after a panic or an invalid argument, and its description
+    /// for the header.
+    fn error_value(&self, ret: &ConvertReturn) -> Result<(TokenStream, String), Error> {
+        Ok(match (&self.error_value, &ret.error_value) {
+            (Some(value), _) => {
+                let expr: Expr = parse_str(value)?;
+                // Literals like `-1` are also C, while other expressions are quoted.
+                let doc = match &expr {
+                    Expr::Lit(_) => value.clone(),
+                    Expr::Unary(ExprUnary {
+                        op: UnOp::Neg(_),
+                        expr,
+                        ..
+                    }) if matches!(**expr, Expr::Lit(_)) => value.replace(' ', ""),
+                    _ => format!("`{value}`"),
+                };
+                (expr.into_token_stream(), doc)
+            }
+            (None, Some(value)) => {
+                let doc = ret
+                    .error_doc
+                    .clone()
+                    .unwrap_or_else(|| format!("`{value}`"));
+                (value.clone(), doc)
+            }
+            (None, None) if ret.c_type == "void" => (quote! {}, String::new()),
+            (None, None) => {
+                return Err(format_err!(
+                    "Coastal has no value for '{}' to return after a panic, so '{}' needs \
+                     #[coast(error_value = ...)]",
+                    self.return_type,
+                    self.name
+                ))
+            }
+        })
+    }
+
+    /// Converts each argument, naming any callback types after this function.
+    fn convert_arguments(&self, lib: &Library) -> Result<Vec<ConvertArg>, Error> {
+        let function = self.c_name(lib);
+        self.arguments
+            .iter()
+            .map(|argument| {
+                let n = Ident::new(&argument.name, Span::call_site());
+                let arg_type: Type = parse_str(&argument.ty)?;
+                let options = ArgOptions {
+                    function: function.clone(),
+                    ..argument.options.clone()
+                };
+                lib.convert_arg(&n, &arg_type, &options)
+            })
+            .collect()
+    }
+
+    /// Returns the options for converting the return type, with the names of the arguments.
+    pub fn return_options(&self) -> ReturnOptions {
+        ReturnOptions {
+            arguments: self.arguments.iter().map(|arg| arg.name.clone()).collect(),
+            ..self.return_options.clone()
+        }
+    }
+
+    /// Returns the C typedefs used by the arguments, such as callback types.
+    pub fn c_typedefs(&self, lib: &Library) -> Result<Vec<(String, String)>, Error> {
+        Ok(self
+            .convert_arguments(lib)?
+            .into_iter()
+            .flat_map(|arg| arg.typedefs)
+            .collect())
+    }
+
+    /// Returns the C prototype for the wrapper, without a trailing semicolon.
+    pub fn c_prototype(&self, lib: &Library) -> Result<String, Error> {
+        let mut c_args = Vec::new();
+        let mut docs = Vec::new();
+        for arg in self.convert_arguments(lib)? {
+            c_args.extend(arg.c_args);
+            docs.extend(arg.doc);
+        }
+        let ret = lib.convert_return(&parse_str(&self.return_type)?, &self.return_options())?;
+        c_args.extend(ret.c_args.iter().cloned());
+        let c_args = if c_args.is_empty() {
+            "void".to_owned()
+        } else {
+            c_args.join(", ")
+        };
+        let prototype = format!(
+            "{}{} {}({c_args})",
+            self.options.c_attributes(lib),
+            ret.c_type,
+            self.c_name(lib)
+        );
+        docs.extend(ret.doc.clone());
+        let mut doc = docs.join(" ");
+        let (_, error_doc) = self.error_value(&ret)?;
+        if !error_doc.is_empty() && self.panic.unwrap_or(lib.panic) != PanicPolicy::Abort {
+            doc.push_str(&format!(" Returns {error_doc} if the function panics."));
+        }
+        let self_type = self.self_type.as_deref();
+        let params: Vec<String> = self
+            .arguments
+            .iter()
+            .filter_map(|arg| {
+                let doc = c_doc(lib, arg.doc.as_ref()?, self_type);
+                Some(format!("@param {} {doc}", arg.name))
+            })
+            .collect();
+        Ok(
+            match self.options.c_comment(lib, self_type, &doc, &params) {
+                Some(comment) => format!("{comment}\n{prototype}"),
+                None => prototype,
+            },
+        )
+    }
 }
 
 impl State for Function {
     const TYPE_NAME: &'static str = "coastal.function";
 }
 
+/// The exported associated functions and methods of a type, from one or more `impl` blocks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Impl {
+    pub self_type: String,
+    pub functions: Vec<Function>,
+}
+
+impl State for Impl {
+    const TYPE_NAME: &'static str = "coastal.impl";
+}
+
+#[derive(Default)]
 pub struct ConvertArg {
     pub decl: TokenStream,
     pub call: TokenStream,
     pub c_args: Vec<String>,
+    /// C typedefs used by `c_args`, as `(name, definition)`, which the header defines once.
+    pub typedefs: Vec<(String, String)>,
+    /// Describes what C must pass in the header, when the C type doesn't say.
+    pub doc: Option<String>,
 }
 
+#[derive(Default)]
 pub struct ConvertReturn {
     pub before: TokenStream,
     pub after: TokenStream,
     pub return_type: TokenStream,
     pub c_type: String,
+    /// Trailing out-parameters, such as `out: *mut T,` for `Result<T, E>`.
+    pub decl: TokenStream,
+    pub c_args: Vec<String>,
+    /// What the wrapper returns after a panic or an invalid argument, which every converted type
+    /// other than `void` needs, since zero isn't valid for every type.
+    pub error_value: Option<TokenStream>,
+    /// Describes `error_value` in the header, such as `NULL` or `-1`.
+    pub error_doc: Option<String>,
+    /// Describes the C return value in the header.
+    pub doc: Option<String>,
+    /// Whether C frees the returned string with `prefix_string_free()`.
+    pub frees_string: bool,
+    /// The struct of a returned slice, which the library defines along with its free function.
+    pub slice: Option<SliceType>,
 }
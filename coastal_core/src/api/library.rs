@@ -1,25 +1,49 @@
+use std::io::Write;
+use std::path::PathBuf;
+
 use convert_case::{Case, Casing};
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, ToTokens};
-use syn::Error;
-use syn::{ReturnType, Type};
+use syn::{parse_str, Error};
+use syn::{ReturnType, Type, TypeGroup, TypeParen, TypePath};
 
 use crate::{
     format_err,
-    types::{convert_builtin_arg, convert_builtin_return},
+    types::{c_value_type, convert_builtin_arg, convert_builtin_return},
 };
 
-use super::{Constant, ConvertArg, ConvertReturn, Function};
+use super::{
+    ArgOptions, Constant, ConstantStyle, ConvertArg, ConvertReturn, Enum, Function,
+    InvalidEnumPolicy, NulPolicy, PanicPolicy, ReturnOptions, SliceType, Struct, Utf8Policy,
+};
 
-pub type ArgConverter = Box<dyn Fn(&Ident, &Type) -> Option<ConvertArg>>;
-pub type ReturnConverter = Box<dyn Fn(&ReturnType) -> Option<ConvertReturn>>;
+pub type ArgConverter = Box<dyn Fn(&Library, &Ident, &Type, &ArgOptions) -> Option<ConvertArg>>;
+pub type ReturnConverter =
+    Box<dyn Fn(&Library, &ReturnType, &ReturnOptions) -> Option<ConvertReturn>>;
 
 pub struct Library {
     pub type_prefix: String,
     pub function_prefix: String,
     pub constant_prefix: String,
+    /// Where to write the C header, or `None` to skip writing it.
+    pub header: Option<PathBuf>,
+    /// The include guard macro, defaulting to the constant prefix followed by `H`.
+    pub guard: Option<String>,
+    pub constant_style: ConstantStyle,
+    /// What wrappers do when a function panics, unless the function overrides it.
+    pub panic: PanicPolicy,
+    /// What to do with string arguments that aren't valid UTF-8, unless the argument overrides it.
+    pub invalid_utf8: Utf8Policy,
+    /// What to do with returned strings that contain NUL characters.
+    pub interior_nul: NulPolicy,
+    /// Whether to keep errors in a thread-local for `prefix_last_error_message()` and friends.
+    pub last_error: bool,
+    /// What to do with invalid enum values from C, unless the enum overrides it.
+    pub invalid_enum: InvalidEnumPolicy,
     pub constants: Vec<Constant>,
     pub functions: Vec<Function>,
+    pub structs: Vec<Struct>,
+    pub enums: Vec<Enum>,
     pub arg_converters: Vec<ArgConverter>,
     pub return_converters: Vec<ReturnConverter>,
 }
@@ -27,50 +51,489 @@ pub struct Library {
 impl Library {
     pub fn new() -> Self {
         let pkg_name = std::env::var("CARGO_PKG_NAME").unwrap_or_else(|_| "package".to_owned());
-        Self {
-            type_prefix: pkg_name.to_case(Case::Pascal),
-            function_prefix: format!("{}_", pkg_name.to_case(Case::Snake)),
-            constant_prefix: format!("{}_", pkg_name.to_case(Case::UpperSnake)),
+        let header = std::env::var_os("OUT_DIR")
+            .map(|out_dir| PathBuf::from(out_dir).join(format!("{pkg_name}.h")));
+        let mut lib = Self {
+            type_prefix: String::new(),
+            function_prefix: String::new(),
+            constant_prefix: String::new(),
+            header,
+            guard: None,
+            constant_style: ConstantStyle::default(),
+            panic: PanicPolicy::default(),
+            invalid_utf8: Utf8Policy::default(),
+            interior_nul: NulPolicy::default(),
+            last_error: false,
+            invalid_enum: InvalidEnumPolicy::default(),
+            constants: Vec::new(),
+            functions: Vec::new(),
+            structs: Vec::new(),
+            enums: Vec::new(),
             arg_converters: vec![Box::new(convert_builtin_arg)],
             return_converters: vec![Box::new(convert_builtin_return)],
-            ..Default::default()
-        }
+        };
+        lib.set_prefix(&pkg_name);
+        lib
+    }
+
+    /// Sets the type, function and constant prefixes from one name, e.g. `my_lib` gives
+    /// `MyLib`, `my_lib_` and `MY_LIB_`.
+    pub fn set_prefix(&mut self, name: &str) {
+        self.type_prefix = name.to_case(Case::Pascal);
+        self.function_prefix = format!("{}_", name.to_case(Case::Snake));
+        self.constant_prefix = format!("{}_", name.to_case(Case::UpperSnake));
     }
 
     pub fn rust_wrapper(&self) -> Result<TokenStream, Error> {
         let mut output = TokenStream::new();
+        for structure in &self.structs {
+            output.extend(structure.rust_wrapper(self));
+        }
         for function in &self.functions {
             output.extend(function.rust_wrapper(self)?);
         }
+        if self.uses_string_free() {
+            output.extend(self.string_free_wrapper());
+        }
+        let slice_types = self.slice_types();
+        for slice in &slice_types {
+            output.extend(slice.rust_wrapper(self));
+        }
+        if self.uses_last_error() {
+            output.extend(self.last_error_wrapper());
+        }
+        let mut types = TokenStream::new();
+        for enumeration in self.enums.iter().filter(|e| e.has_fields()) {
+            types.extend(enumeration.rust_types());
+        }
+        for slice in &slice_types {
+            types.extend(slice.rust_types()?);
+        }
+        if !types.is_empty() {
+            output.extend(quote! {
+                /// The C layout of enums with fields and returned slices.
+                #[allow(dead_code)]
+                mod coastal_types {
+                    #[allow(unused_imports)]
+                    use super::*;
+
+                    #types
+                }
+            });
+        }
         Ok(quote! {
             mod coastal_wrappers {
+                #[allow(unused_imports)]
+                use super::*;
+
                 #output
             }
         })
     }
 
-    pub fn convert_arg(&self, name: &Ident, arg_type: &Type) -> Result<ConvertArg, Error> {
-        self.arg_converters
+    /// Runs `body` in a wrapper, returning `error_value` or aborting if it panics.
+    pub fn catch_panic(
+        &self,
+        c_name: &str,
+        policy: PanicPolicy,
+        error_value: TokenStream,
+        body: TokenStream,
+    ) -> TokenStream {
+        let on_panic = match policy {
+            PanicPolicy::Abort => {
+                let message = format!("Coastal: '{c_name}' panicked, aborting");
+                quote! {
+                    drop(payload);
+                    eprintln!(#message);
+                    std::process::abort()
+                }
+            }
+            PanicPolicy::Error => quote! {
+                drop(payload);
+                #error_value
+            },
+            PanicPolicy::LastError => quote! {
+                coastal_set_last_error(-1, coastal_panic_message(payload));
+                #error_value
+            },
+        };
+        quote! {
+            match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| { #body })) {
+                Ok(ret) => ret,
+                Err(payload) => { #on_panic }
+            }
+        }
+    }
+
+    /// Returns true if the library has a thread-local last error, either from the `last_error`
+    /// setting or because a function keeps its panic messages there.
+    pub fn uses_last_error(&self) -> bool {
+        self.last_error
+            || self.panic == PanicPolicy::LastError
+            || self
+                .functions
+                .iter()
+                .any(|f| f.panic == Some(PanicPolicy::LastError))
+    }
+
+    /// Returns the conversions of the functions' return types, skipping any that fail.
+    fn function_returns(&self) -> impl Iterator<Item = ConvertReturn> + '_ {
+        self.functions.iter().filter_map(|f| {
+            parse_str(&f.return_type)
+                .ok()
+                .and_then(|ty| self.convert_return(&ty, &f.return_options()).ok())
+        })
+    }
+
+    /// Returns true if any function returns a string for C to free.
+    pub fn uses_string_free(&self) -> bool {
+        self.function_returns().any(|ret| ret.frees_string)
+    }
+
+    /// Returns the slice types returned by the functions, without duplicates.
+    pub fn slice_types(&self) -> Vec<SliceType> {
+        let mut slice_types: Vec<SliceType> = Vec::new();
+        for slice in self.function_returns().filter_map(|ret| ret.slice) {
+            if !slice_types.iter().any(|s| s.name == slice.name) {
+                slice_types.push(slice);
+            }
+        }
+        slice_types
+    }
+
+    /// Returns the name of the macro that marks deprecated functions in the header.
+    pub fn deprecated_macro(&self) -> String {
+        format!("{}DEPRECATED", self.constant_prefix)
+    }
+
+    /// Returns the name of the function that frees strings returned to C.
+    pub fn string_free_name(&self) -> String {
+        format!("{}string_free", self.function_prefix)
+    }
+
+    /// Returns the function that frees strings returned to C.
+    fn string_free_wrapper(&self) -> TokenStream {
+        let free_name = Ident::new(&self.string_free_name(), Span::call_site());
+        quote! {
+            /// Frees a string returned by the library. Does nothing if `string` is null.
+            #[no_mangle]
+            pub extern "C" fn #free_name(string: *mut std::ffi::c_char) {
+                if !string.is_null() {
+                    drop(unsafe { std::ffi::CString::from_raw(string) });
+                }
+            }
+        }
+    }
+
+    /// Returns the names of the functions returning the last error message and code, and
+    /// clearing it.
+    pub fn last_error_names(&self) -> [String; 3] {
+        let prefix = &self.function_prefix;
+        [
+            format!("{prefix}last_error_message"),
+            format!("{prefix}last_error_code"),
+            format!("{prefix}clear_error"),
+        ]
+    }
+
+    /// Returns the thread-local last error and the functions that return it to C.
+    fn last_error_wrapper(&self) -> TokenStream {
+        let [message_name, code_name, clear_name] = self
+            .last_error_names()
+            .map(|name| Ident::new(&name, Span::call_site()));
+        quote! {
+            thread_local! {
+                static COASTAL_LAST_ERROR:
+                    std::cell::RefCell<Option<(std::ffi::c_int, std::ffi::CString)>> =
+                    const { std::cell::RefCell::new(None) };
+            }
+
+            fn coastal_set_last_error(code: std::ffi::c_int, message: String) {
+                let message = std::ffi::CString::new(message.replace('\0', ""))
+                    .expect("NUL characters were removed");
+                COASTAL_LAST_ERROR.with(|last| *last.borrow_mut() = Some((code, message)));
+            }
+
+            #[allow(dead_code)]
+            fn coastal_panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+                if let Some(message) = payload.downcast_ref::<&str>() {
+                    (*message).to_owned()
+                } else if let Some(message) = payload.downcast_ref::<String>() {
+                    message.clone()
+                } else {
+                    "unknown panic".to_owned()
+                }
+            }
+
+            /// Returns the message of the last error on this thread, or null. The string is valid
+            /// until the next error on this thread, or until it's cleared.
+            #[no_mangle]
+            pub extern "C" fn #message_name() -> *const std::ffi::c_char {
+                COASTAL_LAST_ERROR.with(|last| {
+                    last.borrow()
+                        .as_ref()
+                        .map_or(std::ptr::null(), |(_, message)| message.as_ptr())
+                })
+            }
+
+            /// Returns the code of the last error on this thread, -1 for a panic, or 0 for none.
+            #[no_mangle]
+            pub extern "C" fn #code_name() -> std::ffi::c_int {
+                COASTAL_LAST_ERROR.with(|last| last.borrow().as_ref().map_or(0, |(code, _)| *code))
+            }
+
+            /// Clears the last error on this thread.
+            #[no_mangle]
+            pub extern "C" fn #clear_name() {
+                COASTAL_LAST_ERROR.with(|last| *last.borrow_mut() = None);
+            }
+        }
+    }
+
+    /// Writes the complete C header for the library.
+    pub fn c_header(&self, f: &mut impl Write) -> Result<(), Error> {
+        let guard = match &self.guard {
+            Some(guard) => guard.clone(),
+            None => format!("{}H", self.constant_prefix),
+        };
+        let mut definitions = Vec::new();
+        for constant in &self.constants {
+            definitions.push(constant.c_definition(self)?);
+        }
+        let mut types = Vec::new();
+        for structure in self.ordered_structs()? {
+            types.push(structure.c_definition(self)?);
+        }
+        // Enums come after structs, which can be fields of their variants.
+        for enumeration in &self.enums {
+            types.push(enumeration.c_definition(self)?);
+        }
+        let slice_types = self.slice_types();
+        for slice in &slice_types {
+            types.push(slice.c_definition(self)?);
+        }
+        // Callback types are named after their functions and arguments, so they can only clash if
+        // functions are renamed to the same name.
+        let mut typedefs: Vec<(String, String)> = Vec::new();
+        for function in &self.functions {
+            for (name, definition) in function.c_typedefs(self)? {
+                match typedefs.iter().find(|(n, _)| *n == name) {
+                    Some((_, existing)) if *existing != definition => {
+                        return Err(format_err!(
+                            "Coastal callback type '{name}' has different signatures"
+                        ))
+                    }
+                    Some(_) => (),
+                    None => typedefs.push((name, definition)),
+                }
+            }
+        }
+        types.extend(typedefs.into_iter().map(|(_, definition)| definition));
+        let mut prototypes = Vec::new();
+        for structure in &self.structs {
+            prototypes.extend(structure.c_prototypes(self));
+        }
+        for function in &self.functions {
+            prototypes.push(function.c_prototype(self)?);
+        }
+        if self.uses_string_free() {
+            prototypes.push(format!("void {}(char* string)", self.string_free_name()));
+        }
+        for slice in &slice_types {
+            prototypes.push(slice.c_prototype(self));
+        }
+        if self.uses_last_error() {
+            let [message_name, code_name, clear_name] = self.last_error_names();
+            prototypes.push(format!("const char* {message_name}(void)"));
+            prototypes.push(format!("int {code_name}(void)"));
+            prototypes.push(format!("void {clear_name}(void)"));
+        }
+        let io_err = |e| format_err!("Coastal failed to write the header: {e}");
+        writeln!(f, "/* Generated by Coastal, do not edit. */").map_err(io_err)?;
+        writeln!(f, "#ifndef {guard}").map_err(io_err)?;
+        writeln!(f, "#define {guard}").map_err(io_err)?;
+        writeln!(f).map_err(io_err)?;
+        writeln!(f, "#include <stdbool.h>").map_err(io_err)?;
+        writeln!(f, "#include <stddef.h>").map_err(io_err)?;
+        writeln!(f, "#include <stdint.h>").map_err(io_err)?;
+        writeln!(f).map_err(io_err)?;
+        if self
+            .functions
+            .iter()
+            .any(|f| f.options.deprecated.is_some())
+        {
+            let deprecated = self.deprecated_macro();
+            writeln!(f, "#if defined(__GNUC__) || defined(__clang__)").map_err(io_err)?;
+            writeln!(
+                f,
+                "#define {deprecated}(note) __attribute__((deprecated(note)))"
+            )
+            .map_err(io_err)?;
+            writeln!(f, "#elif defined(_MSC_VER)").map_err(io_err)?;
+            writeln!(f, "#define {deprecated}(note) __declspec(deprecated(note))")
+                .map_err(io_err)?;
+            writeln!(f, "#else").map_err(io_err)?;
+            writeln!(f, "#define {deprecated}(note)").map_err(io_err)?;
+            writeln!(f, "#endif").map_err(io_err)?;
+            writeln!(f).map_err(io_err)?;
+        }
+        for definition in &definitions {
+            writeln!(f, "{definition}").map_err(io_err)?;
+        }
+        if !definitions.is_empty() {
+            writeln!(f).map_err(io_err)?;
+        }
+        for definition in &types {
+            writeln!(f, "{definition}\n").map_err(io_err)?;
+        }
+        writeln!(f, "#ifdef __cplusplus").map_err(io_err)?;
+        writeln!(f, "extern \"C\" {{").map_err(io_err)?;
+        writeln!(f, "#endif").map_err(io_err)?;
+        writeln!(f).map_err(io_err)?;
+        for prototype in &prototypes {
+            writeln!(f, "{prototype};").map_err(io_err)?;
+        }
+        if !prototypes.is_empty() {
+            writeln!(f).map_err(io_err)?;
+        }
+        writeln!(f, "#ifdef __cplusplus").map_err(io_err)?;
+        writeln!(f, "}}").map_err(io_err)?;
+        writeln!(f, "#endif").map_err(io_err)?;
+        writeln!(f).map_err(io_err)?;
+        writeln!(f, "#endif /* {guard} */").map_err(io_err)
+    }
+
+    /// Writes the C header to `self.header`, if set.
+    ///
+    /// The header is generated even when it isn't written, so that errors in types and constants
+    /// are always reported. The file is left untouched when its contents haven't changed, so that
+    /// build systems tracking the header don't rebuild needlessly.
+    pub fn write_header(&self) -> Result<(), Error> {
+        let mut header = Vec::new();
+        self.c_header(&mut header)?;
+        let Some(path) = &self.header else {
+            return Ok(());
+        };
+        if std::fs::read(path).is_ok_and(|existing| existing == header) {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format_err!("Coastal failed to create '{}': {e}", parent.display()))?;
+        }
+        std::fs::write(path, header)
+            .map_err(|e| format_err!("Coastal failed to write '{}': {e}", path.display()))
+    }
+
+    /// Returns the structs in an order where each comes after the structs it contains.
+    fn ordered_structs(&self) -> Result<Vec<&Struct>, Error> {
+        fn visit<'a>(
+            lib: &'a Library,
+            structure: &'a Struct,
+            visiting: &mut Vec<&'a str>,
+            ordered: &mut Vec<&'a Struct>,
+        ) -> Result<(), Error> {
+            if ordered.iter().any(|s| s.name == structure.name) {
+                return Ok(());
+            }
+            if visiting.contains(&structure.name.as_str()) {
+                return Err(format_err!(
+                    "Coastal struct '{}' contains itself",
+                    structure.name
+                ));
+            }
+            visiting.push(&structure.name);
+            for dependency in structure.dependencies(lib) {
+                if let Some(s) = lib.structs.iter().find(|s| s.name == dependency) {
+                    visit(lib, s, visiting, ordered)?;
+                }
+            }
+            visiting.pop();
+            ordered.push(structure);
+            Ok(())
+        }
+        let mut ordered = Vec::new();
+        for structure in &self.structs {
+            visit(self, structure, &mut Vec::new(), &mut ordered)?;
+        }
+        Ok(ordered)
+    }
+
+    /// Returns the exported struct named by `ty`, if any, whether it's opaque or not.
+    pub fn find_struct(&self, ty: &Type) -> Option<&Struct> {
+        let name = type_name(ty)?;
+        self.structs.iter().find(|s| s.name == name)
+    }
+
+    /// Returns the exported enum named by `ty`, if any.
+    pub fn find_enum(&self, ty: &Type) -> Option<&Enum> {
+        let name = type_name(ty)?;
+        self.enums.iter().find(|e| e.name == name)
+    }
+
+    /// Returns the C type for a Rust type that can be passed by value without conversion.
+    pub fn c_value_type(&self, ty: &Type) -> Option<String> {
+        c_value_type(self, ty)
+    }
+
+    /// Returns the exported constant with the Rust name `name`.
+    pub fn constant(&self, name: &str) -> Result<&Constant, Error> {
+        self.constants
             .iter()
-            .find_map(|ac| ac(name, arg_type))
+            .find(|c| c.name == name)
+            .ok_or_else(|| format_err!("Coastal constant '{name}' must also be listed in api!"))
+    }
+
+    pub fn convert_arg(
+        &self,
+        name: &Ident,
+        arg_type: &Type,
+        options: &ArgOptions,
+    ) -> Result<ConvertArg, Error> {
+        let mut arg = self
+            .arg_converters
+            .iter()
+            .find_map(|ac| ac(self, name, arg_type, options))
             .ok_or_else(|| {
                 format_err!(
                     "can't convert argument '{name}: {}'",
                     arg_type.to_token_stream().to_string()
                 )
-            })
+            })?;
+        if let Some(c_type) = &options.c_type {
+            match &mut arg.c_args[..] {
+                [c_arg] => *c_arg = format!("{c_type} {name}"),
+                _ => {
+                    return Err(format_err!(
+                        "can't override the C type of argument '{name}', which is passed as {} \
+                         C parameters",
+                        arg.c_args.len()
+                    ))
+                }
+            }
+        }
+        Ok(arg)
     }
 
-    pub fn convert_return(&self, return_type: &ReturnType) -> Result<ConvertReturn, Error> {
-        self.return_converters
+    pub fn convert_return(
+        &self,
+        return_type: &ReturnType,
+        options: &ReturnOptions,
+    ) -> Result<ConvertReturn, Error> {
+        let mut ret = self
+            .return_converters
             .iter()
-            .find_map(|rc| rc(return_type))
+            .find_map(|rc| rc(self, return_type, options))
             .ok_or_else(|| {
                 format_err!(
                     "can't convert return type '{}'",
                     return_type.to_token_stream().to_string()
                 )
-            })
+            })?;
+        if let Some(c_type) = &options.c_type {
+            ret.c_type = c_type.clone();
+        }
+        Ok(ret)
     }
 }
 
@@ -79,3 +542,20 @@ impl Default for Library {
         Self::new()
     }
 }
+
+/// Returns the final identifier of a path type such as `Foo` or `crate::geom::Foo`.
+pub(crate) fn type_name(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Group(TypeGroup { elem, .. }) | Type::Paren(TypeParen { elem, .. }) => {
+            type_name(elem)
+        }
+        Type::Path(TypePath { qself: None, path }) => {
+            let segment = path.segments.last()?;
+            segment
+                .arguments
+                .is_none()
+                .then(|| segment.ident.to_string())
+        }
+        _ => None,
+    }
+}
@@ -1,9 +1,25 @@
 mod constant;
+mod doc;
+mod enumeration;
+mod expr;
 mod function;
 mod library;
+mod slice;
 mod state;
+mod structure;
 
-pub use constant::{Constant, ConstantValue};
-pub use function::{ConvertArg, ConvertReturn, Function};
+pub use constant::{Constant, ConstantStyle, ConstantValue};
+pub(crate) use doc::split_arguments;
+pub(crate) use enumeration::int_literal;
+pub use enumeration::{Enum, InvalidEnumPolicy, Variant};
+pub(crate) use expr::int_type;
+pub use expr::{BinaryOp, ConstExpr, Number, UnaryOp};
+pub use function::{
+    ArgOptions, Argument, ConvertArg, ConvertReturn, Function, Impl, Instantiation, ItemOptions,
+    NulPolicy, PanicPolicy, ReturnOptions, Utf8Policy,
+};
+pub(crate) use library::type_name;
 pub use library::Library;
+pub use slice::SliceType;
 pub use state::State;
+pub use structure::Struct;
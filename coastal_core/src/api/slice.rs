@@ -0,0 +1,121 @@
+use convert_case::{Case, Casing};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::{parse_str, Error, Type};
+
+use crate::format_err;
+
+use super::{type_name, Library};
+
+/// A struct of a pointer and a length, for returning `Vec<T>` and `Box<[T]>` to C.
+#[derive(Debug, Clone)]
+pub struct SliceType {
+    /// The element type.
+    pub elem: String,
+    /// The name of the element type in the name of the struct, e.g. `Point` or `U32`.
+    pub name: String,
+    /// The name of the element type in the name of the free function, e.g. `point` or `u32`.
+    pub snake_name: String,
+}
+
+impl SliceType {
+    /// Returns the slice type for elements of `elem`, if they can be passed to C without
+    /// conversion.
+    pub fn new(lib: &Library, elem: &Type) -> Option<Self> {
+        lib.c_value_type(elem)?;
+        // Converting the case of `u32` would separate the digits, so only structs are converted.
+        let (name, snake_name) = match lib.find_struct(elem) {
+            Some(s) => (s.name.clone(), s.name.to_case(Case::Snake)),
+            None => {
+                let name = type_name(elem)?;
+                (name.to_case(Case::Pascal), name)
+            }
+        };
+        Some(Self {
+            elem: quote! { #elem }.to_string(),
+            name,
+            snake_name,
+        })
+    }
+
+    /// Returns the name of the Rust struct in the `coastal_types` module, e.g. `PointSlice`.
+    pub fn rust_name(&self) -> Ident {
+        Ident::new(&format!("{}Slice", self.name), Span::call_site())
+    }
+
+    /// Returns the prefixed C name of the struct, e.g. `MyLibPointSlice`.
+    pub fn c_name(&self, lib: &Library) -> String {
+        format!("{}{}Slice", lib.type_prefix, self.name)
+    }
+
+    /// Returns the name of the generated function that frees the elements.
+    pub fn free_name(&self, lib: &Library) -> String {
+        format!("{}{}_slice_free", lib.function_prefix, self.snake_name)
+    }
+
+    /// Returns the `#[repr(C)]` Rust struct, which goes in the `coastal_types` module.
+    pub fn rust_types(&self) -> Result<TokenStream, Error> {
+        let name = self.rust_name();
+        let elem: Type = parse_str(&self.elem)?;
+        Ok(quote! {
+            #[repr(C)]
+            pub struct #name {
+                pub ptr: *mut #elem,
+                pub len: usize,
+            }
+
+            impl #name {
+                /// Gives the elements to C, which frees them with the generated function. An
+                /// empty slice is null, since it has no allocation.
+                pub fn from_boxed(slice: Box<[#elem]>) -> Self {
+                    let len = slice.len();
+                    if len == 0 {
+                        return Self { ptr: std::ptr::null_mut(), len };
+                    }
+                    Self { ptr: Box::into_raw(slice).cast(), len }
+                }
+            }
+        })
+    }
+
+    /// Returns the generated function that frees the elements.
+    pub fn rust_wrapper(&self, lib: &Library) -> TokenStream {
+        let name = self.rust_name();
+        let free_name = Ident::new(&self.free_name(lib), Span::call_site());
+        // Dropping the elements runs user code, which may panic.
+        let body = lib.catch_panic(
+            &self.free_name(lib),
+            lib.panic,
+            quote! {},
+            quote! {
+                if !slice.ptr.is_null() {
+                    let elements = std::ptr::slice_from_raw_parts_mut(slice.ptr, slice.len);
+                    drop(unsafe { Box::from_raw(elements) });
+                }
+            },
+        );
+        quote! {
+            #[no_mangle]
+            pub extern "C" fn #free_name(slice: coastal_types::#name) {
+                #body
+            }
+        }
+    }
+
+    /// Returns the C `typedef` for the struct.
+    pub fn c_definition(&self, lib: &Library) -> Result<String, Error> {
+        let c_name = self.c_name(lib);
+        let elem: Type = parse_str(&self.elem)?;
+        let c_type = lib
+            .c_value_type(&elem)
+            .ok_or_else(|| format_err!("can't convert slice of '{}' to C", self.elem))?;
+        Ok(format!(
+            "typedef struct {c_name} {{\n    {c_type}* ptr;\n    size_t len;\n}} {c_name};"
+        ))
+    }
+
+    /// Returns the C prototype of the generated function that frees the elements.
+    pub fn c_prototype(&self, lib: &Library) -> String {
+        format!("void {}({} slice)", self.free_name(lib), self.c_name(lib))
+    }
+}
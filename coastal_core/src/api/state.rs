@@ -17,11 +17,12 @@ pub trait State: Serialize + DeserializeOwned {
     }
 
     fn load_state(name: &Ident) -> Result<Self, Error> {
+        let kind = Self::TYPE_NAME.trim_start_matches("coastal.");
         let state = proc_read_state(&format!("{}.{name}", Self::TYPE_NAME))
-            .map_err(|e| format_err!(@name, "Coastal could not find the function '{name}': {e}"))?;
+            .map_err(|e| format_err!(@name, "Coastal could not find the {kind} '{name}': {e}"))?;
         let this = serde_json::from_str(&state).map_err(|e| {
             format_err!(
-                @name, "Coastal failed to deserialise the state of function '{name}': {e}"
+                @name, "Coastal failed to deserialise the state of {kind} '{name}': {e}"
             )
         })?;
         Ok(this)
@@ -0,0 +1,128 @@
+use convert_case::{Case, Casing};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use serde::{Deserialize, Serialize};
+use syn::{parse_str, Error, Type};
+
+use crate::{format_err, types::c_identifier};
+
+use super::{
+    doc::{c_doc, doxygen},
+    Library, State,
+};
+
+/// A `#[repr(C)]` struct passed to and from C by value, or an opaque struct passed by pointer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Struct {
+    pub name: String,
+    pub fields: Vec<(String, String)>,
+    /// Opaque structs are only declared in C, and are owned through `Box`.
+    pub opaque: bool,
+    /// The Markdown documentation from its doc comment.
+    #[serde(default)]
+    pub doc: Option<String>,
+}
+
+impl Struct {
+    /// Returns the prefixed C name of the struct.
+    pub fn c_name(&self, lib: &Library) -> String {
+        format!("{}{}", lib.type_prefix, self.name)
+    }
+
+    /// Returns the name of the generated function that frees an opaque struct.
+    pub fn free_name(&self, lib: &Library) -> String {
+        format!(
+            "{}{}_free",
+            lib.function_prefix,
+            self.name.to_case(Case::Snake)
+        )
+    }
+
+    /// Returns the name of the argument of the free function, which is escaped if it's a keyword.
+    fn free_arg_name(&self) -> String {
+        c_identifier(&self.name.to_case(Case::Snake))
+    }
+
+    /// Returns the generated functions for the struct.
+    pub fn rust_wrapper(&self, lib: &Library) -> TokenStream {
+        if !self.opaque {
+            return TokenStream::new();
+        }
+        let name = Ident::new(&self.name, Span::call_site());
+        let free_name = Ident::new(&self.free_name(lib), Span::call_site());
+        let arg = Ident::new(&self.free_arg_name(), Span::call_site());
+        // Dropping the struct runs user code, which may panic.
+        let body = lib.catch_panic(
+            &self.free_name(lib),
+            lib.panic,
+            quote! {},
+            quote! {
+                if !#arg.is_null() {
+                    drop(unsafe { Box::from_raw(#arg) });
+                }
+            },
+        );
+        quote! {
+            #[no_mangle]
+            pub extern "C" fn #free_name(#arg: *mut #name) {
+                #body
+            }
+        }
+    }
+
+    /// Returns the C prototypes of the generated functions for the struct.
+    pub fn c_prototypes(&self, lib: &Library) -> Vec<String> {
+        if !self.opaque {
+            return Vec::new();
+        }
+        vec![format!(
+            "void {}({}* {})",
+            self.free_name(lib),
+            self.c_name(lib),
+            self.free_arg_name()
+        )]
+    }
+
+    /// Returns the C `typedef` for the struct, after its comment.
+    pub fn c_definition(&self, lib: &Library) -> Result<String, Error> {
+        let definition = self.c_typedef(lib)?;
+        let doc = self
+            .doc
+            .as_ref()
+            .map(|doc| c_doc(lib, doc, Some(&self.name)));
+        Ok(match doc.and_then(|doc| doxygen(&doc, "")) {
+            Some(comment) => format!("{comment}\n{definition}"),
+            None => definition,
+        })
+    }
+
+    /// Returns the C `typedef` for the struct.
+    fn c_typedef(&self, lib: &Library) -> Result<String, Error> {
+        let c_name = self.c_name(lib);
+        if self.opaque {
+            return Ok(format!("typedef struct {c_name} {c_name};"));
+        }
+        let mut definition = format!("typedef struct {c_name} {{\n");
+        for (name, ty) in &self.fields {
+            let field_type: Type = parse_str(ty)?;
+            let c_type = lib.c_value_type(&field_type).ok_or_else(|| {
+                format_err!("can't convert field '{name}: {ty}' of '{}' to C", self.name)
+            })?;
+            definition.push_str(&format!("    {c_type} {};\n", c_identifier(name)));
+        }
+        definition.push_str(&format!("}} {c_name};"));
+        Ok(definition)
+    }
+
+    /// Returns the names of exported structs this struct contains by value.
+    pub fn dependencies<'a>(&'a self, lib: &'a Library) -> impl Iterator<Item = &'a str> {
+        self.fields.iter().filter_map(|(_, ty)| {
+            let ty: Type = parse_str(ty).ok()?;
+            lib.find_struct(&ty).map(|s| s.name.as_str())
+        })
+    }
+}
+
+impl State for Struct {
+    const TYPE_NAME: &'static str = "coastal.struct";
+}
@@ -1,11 +1,21 @@
+use std::path::PathBuf;
+
 use proc_macro2::TokenStream;
-use syn::{parse::Parse, parse2, token, Error, Ident};
+use syn::{parse::Parse, parse2, token, Error, Ident, Lit, LitBool, LitStr};
 
-use crate::api::{Constant, Function, Library, State};
+use crate::{
+    api::{
+        Constant, ConstantStyle, Enum, Function, Impl, InvalidEnumPolicy, Library, NulPolicy,
+        PanicPolicy, State, Struct, Utf8Policy,
+    },
+    format_err,
+};
 
 /// Implementation for `coastal_derive::coast!`.
 pub fn api(input: TokenStream) -> Result<TokenStream, Error> {
     let api: Api = parse2(input)?;
+    // The header comes first, since it reports problems like invalid settings more clearly.
+    api.library.write_header()?;
     api.library.rust_wrapper()
 }
 
@@ -14,6 +24,51 @@ struct Api {
     library: Library,
 }
 
+impl Api {
+    /// Applies a `key = value;` setting to the library.
+    fn apply_setting(&mut self, key: &Ident, value: &Lit) -> Result<(), Error> {
+        let lib = &mut self.library;
+        match key.to_string().as_str() {
+            "prefix" => lib.set_prefix(&lit_str(value)?.value()),
+            "type_prefix" => lib.type_prefix = lit_str(value)?.value(),
+            "function_prefix" => lib.function_prefix = lit_str(value)?.value(),
+            "constant_prefix" => lib.constant_prefix = lit_str(value)?.value(),
+            "header" => lib.header = Some(manifest_path(&lit_str(value)?.value())),
+            "guard" => lib.guard = Some(lit_str(value)?.value()),
+            "constant_style" => {
+                lib.constant_style = match lit_str(value)?.value().as_str() {
+                    "define" => ConstantStyle::Define,
+                    "static" => ConstantStyle::StaticConst,
+                    "enum" => ConstantStyle::Enum,
+                    _ => {
+                        return Err(format_err!(
+                            @value, "expected \"define\", \"static\" or \"enum\""
+                        ))
+                    }
+                }
+            }
+            "panic" => lib.panic = panic_policy(lit_str(value)?)?,
+            "last_error" => lib.last_error = lit_bool(value)?.value,
+            "invalid_enum" => lib.invalid_enum = invalid_enum_policy(lit_str(value)?)?,
+            "invalid_utf8" => lib.invalid_utf8 = utf8_policy(lit_str(value)?)?,
+            "interior_nul" => {
+                lib.interior_nul = match lit_str(value)?.value().as_str() {
+                    "panic" => NulPolicy::Panic,
+                    "error" => NulPolicy::Error,
+                    "truncate" => NulPolicy::Truncate,
+                    _ => {
+                        return Err(format_err!(
+                            @value, "expected \"panic\", \"error\" or \"truncate\""
+                        ))
+                    }
+                }
+            }
+            _ => return Err(format_err!(@key, "unknown Coastal setting '{key}'")),
+        }
+        Ok(())
+    }
+}
+
 impl Parse for Api {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let mut api = Api::default();
@@ -23,12 +78,41 @@ impl Parse for Api {
                 input.parse::<token::Fn>()?;
                 let ident: Ident = input.parse()?;
                 input.parse::<token::Semi>()?;
-                api.library.functions.push(Function::load_state(&ident)?)
+                let function = Function::load_state(&ident)?;
+                if !function.options.skip {
+                    api.library.functions.extend(function.instantiate()?);
+                }
             } else if lookahead.peek(token::Const) {
                 input.parse::<token::Const>()?;
                 let ident: Ident = input.parse()?;
                 input.parse::<token::Semi>()?;
-                api.library.constants.push(Constant::load_state(&ident)?)
+                let constant = Constant::load_state(&ident)?;
+                if !constant.options.skip {
+                    api.library.constants.push(constant);
+                }
+            } else if lookahead.peek(token::Struct) {
+                input.parse::<token::Struct>()?;
+                let ident: Ident = input.parse()?;
+                input.parse::<token::Semi>()?;
+                api.library.structs.push(Struct::load_state(&ident)?)
+            } else if lookahead.peek(token::Enum) {
+                input.parse::<token::Enum>()?;
+                let ident: Ident = input.parse()?;
+                input.parse::<token::Semi>()?;
+                api.library.enums.push(Enum::load_state(&ident)?)
+            } else if lookahead.peek(token::Impl) {
+                input.parse::<token::Impl>()?;
+                let ident: Ident = input.parse()?;
+                input.parse::<token::Semi>()?;
+                for function in Impl::load_state(&ident)?.functions {
+                    api.library.functions.extend(function.instantiate()?);
+                }
+            } else if lookahead.peek(Ident) {
+                let key: Ident = input.parse()?;
+                input.parse::<token::Eq>()?;
+                let value: Lit = input.parse()?;
+                input.parse::<token::Semi>()?;
+                api.apply_setting(&key, &value)?;
             } else {
                 return Err(lookahead.error());
             }
@@ -36,3 +120,61 @@ impl Parse for Api {
         Ok(api)
     }
 }
+
+fn lit_str(lit: &Lit) -> Result<&LitStr, Error> {
+    match lit {
+        Lit::Str(s) => Ok(s),
+        _ => Err(format_err!(@lit, "expected a string")),
+    }
+}
+
+/// Parses the policy for panics, used by `api!` and `#[coast]`.
+pub(crate) fn panic_policy(value: &LitStr) -> Result<PanicPolicy, Error> {
+    match value.value().as_str() {
+        "abort" => Ok(PanicPolicy::Abort),
+        "error" => Ok(PanicPolicy::Error),
+        "last_error" => Ok(PanicPolicy::LastError),
+        _ => Err(format_err!(
+            @value, "expected \"abort\", \"error\" or \"last_error\""
+        )),
+    }
+}
+
+/// Parses the policy for invalid enum values, used by `api!` and `#[coast]`.
+pub(crate) fn invalid_enum_policy(value: &LitStr) -> Result<InvalidEnumPolicy, Error> {
+    match value.value().as_str() {
+        "abort" => Ok(InvalidEnumPolicy::Abort),
+        "error" => Ok(InvalidEnumPolicy::Error),
+        "default" => Ok(InvalidEnumPolicy::Default),
+        _ => Err(format_err!(
+            @value, "expected \"abort\", \"error\" or \"default\""
+        )),
+    }
+}
+
+/// Parses the policy for string arguments that aren't UTF-8, used by `api!` and `#[coast]`.
+pub(crate) fn utf8_policy(value: &LitStr) -> Result<Utf8Policy, Error> {
+    match value.value().as_str() {
+        "panic" => Ok(Utf8Policy::Panic),
+        "error" => Ok(Utf8Policy::Error),
+        "lossy" => Ok(Utf8Policy::Lossy),
+        _ => Err(format_err!(
+            @value, "expected \"panic\", \"error\" or \"lossy\""
+        )),
+    }
+}
+
+fn lit_bool(lit: &Lit) -> Result<&LitBool, Error> {
+    match lit {
+        Lit::Bool(b) => Ok(b),
+        _ => Err(format_err!(@lit, "expected 'true' or 'false'")),
+    }
+}
+
+/// Resolves `path` relative to the directory of the crate being compiled.
+fn manifest_path(path: &str) -> PathBuf {
+    match std::env::var_os("CARGO_MANIFEST_DIR") {
+        Some(dir) => PathBuf::from(dir).join(path),
+        None => PathBuf::from(path),
+    }
+}
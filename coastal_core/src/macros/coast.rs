@@ -1,53 +1,521 @@
-use proc_macro2::TokenStream;
-use quote::ToTokens;
+use std::{ops::Neg, str::FromStr};
+
+use convert_case::{Case, Casing};
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
 use syn::{
-    Error, Expr, ExprLit, FnArg, Item, ItemConst, ItemFn, Lit, Pat, PatType, Type, TypeImplTrait,
+    parse::Parser, punctuated::Punctuated, token, Attribute, BinOp, Error, Expr, ExprBinary,
+    ExprCast, ExprGroup, ExprLit, ExprParen, ExprPath, ExprUnary, Fields, FnArg, GenericArgument,
+    GenericParam, ImplItem, Item, ItemConst, ItemEnum, ItemFn, ItemImpl, ItemStruct, Lit, LitInt,
+    LitStr, Meta, Pat, PatType, Path, PathArguments, ReturnType, Signature, Token, TraitBound,
+    Type, TypeArray, TypeGroup, TypeImplTrait, TypeParamBound, TypeParen, TypePath, TypePtr,
+    TypeReference, TypeSlice, UnOp, Visibility,
 };
 
+use super::api::{invalid_enum_policy, panic_policy, utf8_policy};
 use crate::{
-    api::{Constant, ConstantValue, Function, State},
+    api::{
+        int_literal, int_type, split_arguments, ArgOptions, Argument, BinaryOp, ConstExpr,
+        Constant, ConstantValue, Enum, Function, Impl, Instantiation, InvalidEnumPolicy,
+        ItemOptions, State, Struct, UnaryOp, Variant,
+    },
     format_err,
+    types::{c_identifier, replace_idents},
 };
 
 /// Implementation for `coastal_derive::coast!`.
 pub fn coast(attr: TokenStream, input: TokenStream) -> Result<TokenStream, Error> {
-    if !attr.is_empty() {
-        return Err(format_err!(
-            @attr, "Coastal attribute macro does not take arguments"
-        ));
-    }
+    let args = CoastArgs::parse(attr)?;
     let item: Item = syn::parse2(input.clone())?;
+    if let (Some(opaque), false) = (&args.opaque, matches!(item, Item::Struct(_))) {
+        return Err(format_err!(@opaque, "only structs can be opaque"));
+    }
+    if let (Some(invalid), false) = (&args.invalid, matches!(item, Item::Enum(_))) {
+        return Err(format_err!(@invalid, "only enums have an 'invalid' policy"));
+    }
+    if let (Some(error), false) = (&args.error, matches!(item, Item::Enum(_))) {
+        return Err(format_err!(@error, "only enums can be error codes"));
+    }
+    if !matches!(item, Item::Fn(_) | Item::Impl(_)) {
+        args.check_function_only()?;
+    }
+    if !matches!(item, Item::Fn(_) | Item::Impl(_) | Item::Const(_)) {
+        args.check_item_only()?;
+    }
     match item {
-        Item::Const(item_const) => handle_const(item_const)?,
-        Item::Fn(item_fn) => handle_fn(item_fn)?,
+        Item::Const(item_const) => handle_const(item_const, &args)?,
+        Item::Enum(item_enum) => {
+            let error_impl = handle_enum(item_enum, &args)?;
+            return Ok(quote! { #input #error_impl });
+        }
+        Item::Fn(item_fn) => return Ok(handle_fn(item_fn, &args)?.into_token_stream()),
+        Item::Impl(item_impl) => return Ok(handle_impl(item_impl, &args)?.into_token_stream()),
+        Item::Struct(item_struct) => handle_struct(item_struct, &args)?,
         _ => {
-            return Err(
-                format_err!(@item, "#[coast] only supports 'const' and 'fn' items currently"),
-            )
+            return Err(format_err!(
+                @item, "#[coast] only supports 'const', 'enum', 'fn', 'impl' and 'struct' items currently"
+            ))
         }
     }
     Ok(input)
 }
 
-fn handle_fn(item_fn: ItemFn) -> Result<(), Error> {
-    if let Some(async_keyword) = item_fn.sig.asyncness {
+/// Arguments to the `#[coast(...)]` attribute.
+#[derive(Default)]
+struct CoastArgs {
+    /// `opaque`: pass a struct to C as a pointer to an incomplete type.
+    opaque: Option<Path>,
+    /// `invalid = "abort" | "error" | "default"`: what to do with invalid enum values from C.
+    invalid: Option<LitStr>,
+    /// `error`: implement `coastal::IntoCError` for a fieldless enum, using its values as codes.
+    error: Option<Path>,
+    /// `panic = "abort" | "error" | "last_error"`: what to do if a function panics.
+    panic: Option<LitStr>,
+    /// `error_value = expr`: what a function returns to C after a panic.
+    error_value: Option<Expr>,
+    /// `unsafe`: allow raw pointers in a function that isn't an `unsafe fn`.
+    allow_unsafe: Option<Path>,
+    /// `out = "a, b"`: the names of the out-parameters for a returned tuple.
+    out: Option<LitStr>,
+    /// `c_type = "..."`: overrides the C return type of a function.
+    c_type: Option<LitStr>,
+    /// `name = "..."`: the exported C name of a function or constant.
+    name: Option<LitStr>,
+    /// `skip`: leave a function or constant out of the library.
+    skip: Option<Path>,
+    /// `deprecated` or `deprecated = "..."`: marks a function or constant as deprecated.
+    deprecated: Option<(Path, Option<LitStr>)>,
+    /// `since = "..."`: the version that added a function or constant.
+    since: Option<LitStr>,
+    /// `doc = "..."`: documents a function or constant in the header, instead of its doc comment.
+    doc: Option<LitStr>,
+    /// `export`: marks a method to export, like a bare `#[coast]`, so that only the marked methods
+    /// of the `impl` block are exported.
+    export: Option<Path>,
+    /// `instantiate(T = f32, suffix = "_f32")`: exports an instantiation of a generic function.
+    instantiate: Vec<InstantiateArgs>,
+}
+
+/// The arguments of an `instantiate(...)` option.
+struct InstantiateArgs {
+    path: Path,
+    /// The argument for each generic parameter, such as `T = f32` or `N = 4`.
+    arguments: Vec<(Ident, GenericArgument)>,
+    /// `suffix = "..."`: added to the C name of the function.
+    suffix: Option<LitStr>,
+}
+
+impl CoastArgs {
+    fn parse(attr: TokenStream) -> Result<Self, Error> {
+        let mut args = CoastArgs::default();
+        let parser = syn::meta::parser(|meta| {
+            if meta.path.is_ident("opaque") {
+                args.opaque = Some(meta.path);
+                Ok(())
+            } else if meta.path.is_ident("invalid") {
+                args.invalid = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("error") {
+                args.error = Some(meta.path);
+                Ok(())
+            } else if meta.path.is_ident("panic") {
+                args.panic = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("error_value") {
+                args.error_value = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("unsafe") {
+                args.allow_unsafe = Some(meta.path);
+                Ok(())
+            } else if meta.path.is_ident("out") {
+                args.out = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("c_type") {
+                args.c_type = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("name") {
+                args.name = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("skip") {
+                args.skip = Some(meta.path);
+                Ok(())
+            } else if meta.path.is_ident("deprecated") {
+                let note = match meta.input.peek(Token![=]) {
+                    true => Some(meta.value()?.parse()?),
+                    false => None,
+                };
+                args.deprecated = Some((meta.path, note));
+                Ok(())
+            } else if meta.path.is_ident("since") {
+                args.since = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("doc") {
+                args.doc = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("export") {
+                args.export = Some(meta.path);
+                Ok(())
+            } else if meta.path.is_ident("instantiate") {
+                let mut instantiate = InstantiateArgs {
+                    path: meta.path.clone(),
+                    arguments: Vec::new(),
+                    suffix: None,
+                };
+                meta.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("suffix") {
+                        instantiate.suffix = Some(meta.value()?.parse()?);
+                    } else if let Some(param) = meta.path.get_ident() {
+                        instantiate
+                            .arguments
+                            .push((param.clone(), meta.value()?.parse()?));
+                    } else {
+                        return Err(meta.error("expected a generic parameter or 'suffix'"));
+                    }
+                    Ok(())
+                })?;
+                args.instantiate.push(instantiate);
+                Ok(())
+            } else {
+                Err(meta.error("unknown Coastal option"))
+            }
+        });
+        Parser::parse2(parser, attr)?;
+        Ok(args)
+    }
+
+    /// Parses the arguments of an inner `#[coast]` or `#[coast(...)]` attribute.
+    fn from_attribute(attr: &Attribute) -> Result<Self, Error> {
+        match &attr.meta {
+            Meta::Path(_) => Ok(Self::default()),
+            Meta::List(list) => Self::parse(list.tokens.clone()),
+            Meta::NameValue(_) => Err(format_err!(@attr, "expected #[coast] or #[coast(...)]")),
+        }
+    }
+
+    /// Returns an error if there are options that only apply to functions.
+    fn check_function_only(&self) -> Result<(), Error> {
+        if let Some(panic) = &self.panic {
+            return Err(format_err!(@panic, "only functions have a 'panic' policy"));
+        }
+        if let Some(error_value) = &self.error_value {
+            return Err(format_err!(@error_value, "only functions have an 'error_value'"));
+        }
+        if let Some(allow_unsafe) = &self.allow_unsafe {
+            return Err(format_err!(@allow_unsafe, "only functions can be marked 'unsafe'"));
+        }
+        if let Some(out) = &self.out {
+            return Err(format_err!(@out, "only functions have 'out' parameters"));
+        }
+        if let Some(c_type) = &self.c_type {
+            return Err(format_err!(@c_type, "only functions and arguments have a 'c_type'"));
+        }
+        if let Some(instantiate) = self.instantiate.first() {
+            return Err(format_err!(@&instantiate.path, "only functions can be instantiated"));
+        }
+        if let Some(export) = &self.export {
+            return Err(format_err!(@export, "only methods can be marked 'export'"));
+        }
+        Ok(())
+    }
+
+    /// Returns an error if there are options that only apply to functions and constants.
+    fn check_item_only(&self) -> Result<(), Error> {
+        if let Some(name) = &self.name {
+            return Err(format_err!(@name, "only functions and constants can be renamed"));
+        }
+        if let Some(skip) = &self.skip {
+            return Err(format_err!(@skip, "only functions and constants can be skipped"));
+        }
+        if let Some((deprecated, _)) = &self.deprecated {
+            return Err(format_err!(
+                @deprecated, "only functions and constants can be deprecated"
+            ));
+        }
+        if let Some(since) = &self.since {
+            return Err(format_err!(@since, "only functions and constants have a 'since' version"));
+        }
+        if let Some(doc) = &self.doc {
+            return Err(format_err!(@doc, "only functions and constants have a 'doc' option"));
+        }
+        Ok(())
+    }
+
+    /// Returns an error if there are options that only apply to a single function.
+    fn check_impl_block(&self) -> Result<(), Error> {
+        if let Some(name) = &self.name {
+            return Err(format_err!(@name, "only methods, not impl blocks, can be renamed"));
+        }
+        if let Some(skip) = &self.skip {
+            return Err(format_err!(@skip, "only methods, not impl blocks, can be skipped"));
+        }
+        if let Some(doc) = &self.doc {
+            return Err(format_err!(@doc, "only methods, not impl blocks, have a 'doc' option"));
+        }
+        if let Some(c_type) = &self.c_type {
+            return Err(format_err!(@c_type, "only methods, not impl blocks, have a 'c_type'"));
+        }
+        if let Some(instantiate) = self.instantiate.first() {
+            return Err(format_err!(
+                @&instantiate.path, "only methods, not impl blocks, can be instantiated"
+            ));
+        }
+        if let Some(export) = &self.export {
+            return Err(
+                format_err!(@export, "only methods, not impl blocks, can be marked 'export'"),
+            );
+        }
+        Ok(())
+    }
+
+    /// Applies the options shared by exported functions and constants.
+    fn apply_item(&self, options: &mut ItemOptions) -> Result<(), Error> {
+        if let Some(name) = &self.name {
+            if syn::parse_str::<Ident>(&name.value()).is_err() {
+                return Err(format_err!(@name, "'{}' is not a valid C name", name.value()));
+            }
+            options.name = Some(name.value());
+        }
+        if self.skip.is_some() {
+            options.skip = true;
+        }
+        if let Some((_, note)) = &self.deprecated {
+            options.deprecated = Some(note.as_ref().map(LitStr::value).unwrap_or_default());
+        }
+        if let Some(since) = &self.since {
+            options.since = Some(since.value());
+        }
+        if let Some(doc) = &self.doc {
+            options.doc = Some(doc.value());
+        }
+        Ok(())
+    }
+
+    /// Applies the function options to an exported function.
+    fn apply(&self, function: &mut Function) -> Result<(), Error> {
+        self.apply_item(&mut function.options)?;
+        if let Some(c_type) = &self.c_type {
+            function.return_options.c_type = Some(c_type.value());
+        }
+        if let Some(panic) = &self.panic {
+            function.panic = Some(panic_policy(panic)?);
+        }
+        if let Some(error_value) = &self.error_value {
+            function.error_value = Some(error_value.to_token_stream().to_string());
+        }
+        if let Some(out) = &self.out {
+            let names: Vec<String> = out
+                .value()
+                .split(',')
+                .map(|n| n.trim().to_owned())
+                .collect();
+            let tuple_len = match syn::parse_str(&function.return_type)? {
+                ReturnType::Type(_, ty) => match *ty {
+                    Type::Tuple(tuple) => tuple.elems.len(),
+                    _ => 0,
+                },
+                ReturnType::Default => 0,
+            };
+            if names.len() != tuple_len {
+                return Err(format_err!(
+                    @out, "expected a name for each of the {tuple_len} elements of the returned tuple"
+                ));
+            }
+            for name in &names {
+                if syn::parse_str::<Ident>(name).is_err() {
+                    return Err(format_err!(@out, "'{name}' is not a valid parameter name"));
+                }
+                if function.arguments.iter().any(|arg| arg.name == *name) {
+                    return Err(format_err!(@out, "'{name}' is already the name of an argument"));
+                }
+            }
+            function.return_options.out = names;
+        }
+        Ok(())
+    }
+}
+
+/// Exports a function, returning it with the `#[coast(...)]` attributes on its arguments removed.
+fn handle_fn(mut item_fn: ItemFn, args: &CoastArgs) -> Result<ItemFn, Error> {
+    if let Some(export) = &args.export {
+        return Err(format_err!(@export, "only methods can be marked 'export'"));
+    }
+    check_raw_pointers(&item_fn.sig, args.allow_unsafe.is_some())?;
+    let mut function = function_from_sig(&mut item_fn.sig, None)?;
+    document_function(&mut function, &item_fn.attrs);
+    function.instantiations = instantiations(&item_fn.sig, &args.instantiate)?;
+    args.apply(&mut function)?;
+    function.save_state(&item_fn.sig.ident)?;
+    Ok(item_fn)
+}
+
+/// Exports the public methods of an `impl` block, or only those marked `#[coast]` or
+/// `#[coast(export, ...)]` if any are.
+///
+/// Other options on a method, like `#[coast(since = "1.1")]`, don't mark it, so they don't change
+/// which methods are exported. Options on the block apply to all its methods, unless the method's
+/// `#[coast(...)]` overrides them. Returns the block with the inner `#[coast]` attributes removed,
+/// since they've been handled.
+fn handle_impl(mut item_impl: ItemImpl, args: &CoastArgs) -> Result<ItemImpl, Error> {
+    if item_impl.generics.lt_token.is_some() {
+        return Err(format_err!(
+            @&item_impl.generics, "Coastal does not support generic impl blocks"
+        ));
+    }
+    if let Some((_, path, _)) = &item_impl.trait_ {
+        return Err(format_err!(
+            @path, "Coastal does not support trait impl blocks"
+        ));
+    }
+    let self_type = match item_impl.self_ty.as_ref() {
+        Type::Path(TypePath { qself: None, path }) => path.get_ident().cloned(),
+        _ => None,
+    }
+    .ok_or_else(
+        || format_err!(@&item_impl.self_ty, "Coastal impl blocks must be for a named type"),
+    )?;
+    let is_coast = |attr: &Attribute| {
+        attr.path()
+            .segments
+            .last()
+            .is_some_and(|s| s.ident == "coast")
+    };
+    args.check_impl_block()?;
+    let is_export = |attr: &Attribute| {
+        is_coast(attr)
+            && match &attr.meta {
+                Meta::Path(_) => true,
+                _ => CoastArgs::from_attribute(attr).is_ok_and(|args| args.export.is_some()),
+            }
+    };
+    let marked = item_impl.items.iter().any(|item| match item {
+        ImplItem::Fn(method) => method.attrs.iter().any(is_export),
+        _ => false,
+    });
+    let mut functions: Vec<Function> = Vec::new();
+    for item in item_impl.items.iter_mut() {
+        let ImplItem::Fn(method) = item else {
+            continue;
+        };
+        let exported = if marked {
+            method.attrs.iter().any(is_export)
+        } else {
+            matches!(method.vis, Visibility::Public(_))
+        };
+        let mut method_args: Vec<CoastArgs> = Vec::new();
+        for attr in method.attrs.iter().filter(|attr| is_coast(attr)) {
+            let method_arg = CoastArgs::from_attribute(attr)?;
+            if let Some(path) = &method_arg.opaque {
+                return Err(format_err!(@path, "only structs can be opaque"));
+            }
+            if let Some(invalid) = &method_arg.invalid {
+                return Err(format_err!(@invalid, "only enums have an 'invalid' policy"));
+            }
+            if let Some(error) = &method_arg.error {
+                return Err(format_err!(@error, "only enums can be error codes"));
+            }
+            method_args.push(method_arg);
+        }
+        let options = method.attrs.iter().find(|attr| is_coast(attr)).cloned();
+        method.attrs.retain(|attr| !is_coast(attr));
+        if method_args.iter().any(|arg| arg.skip.is_some()) {
+            continue;
+        }
+        if !exported {
+            // Options on a method that isn't exported would be silently ignored.
+            if let Some(options) = options {
+                return Err(if marked {
+                    format_err!(
+                        @options,
+                        "this method isn't exported, since others are marked #[coast]; use \
+                         #[coast(export, ...)]"
+                    )
+                } else {
+                    format_err!(@options, "this method isn't exported, since it isn't public")
+                });
+            }
+            continue;
+        }
+        let allow_unsafe =
+            args.allow_unsafe.is_some() || method_args.iter().any(|arg| arg.allow_unsafe.is_some());
+        check_raw_pointers(&method.sig, allow_unsafe)?;
+        let mut function = function_from_sig(&mut method.sig, Some(&self_type))?;
+        document_function(&mut function, &method.attrs);
+        let instantiate = method_args.iter().flat_map(|arg| &arg.instantiate);
+        function.instantiations = instantiations(&method.sig, instantiate)?;
+        args.apply(&mut function)?;
+        for method_arg in &method_args {
+            method_arg.apply(&mut function)?;
+        }
+        functions.push(function);
+    }
+    // Merge with any other impl blocks for the same type.
+    if let Ok(Impl {
+        functions: existing,
+        ..
+    }) = Impl::load_state(&self_type)
+    {
+        for function in existing {
+            if !functions.iter().any(|f| f.name == function.name) {
+                functions.push(function);
+            }
+        }
+    }
+    Impl {
+        self_type: self_type.to_string(),
+        functions,
+    }
+    .save_state(&self_type)?;
+    Ok(item_impl)
+}
+
+/// Reads the signature of a function, or of a method of `self_type`.
+///
+/// The `#[coast(...)]` attributes on arguments are removed, since Rust doesn't allow them.
+fn function_from_sig(sig: &mut Signature, self_type: Option<&Ident>) -> Result<Function, Error> {
+    if let Some(async_keyword) = sig.asyncness {
         return Err(format_err!(
             @async_keyword,
             "Coastal does not support async functions"
         ));
     }
-    if item_fn.sig.generics.lt_token.is_some() {
-        return Err(format_err!(@item_fn.sig.generics, "Coastal does not support async functions"));
-    }
+    let type_string = |ty: &Type| match self_type {
+        Some(self_type) => replace_self(ty.to_token_stream(), self_type).to_string(),
+        None => ty.to_token_stream().to_string(),
+    };
     let mut arguments = Vec::new();
-    for arg in item_fn.sig.inputs.iter() {
+    let names: Vec<String> = sig
+        .inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            FnArg::Typed(PatType { pat, .. }) => match pat.as_ref() {
+                Pat::Ident(p) => Some(p.ident.to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    for arg in sig.inputs.iter_mut() {
         match arg {
-            FnArg::Receiver(token) => {
-                return Err(format_err!(
-                    @token, "Coastal does not yet support associated methods"
-                ));
+            FnArg::Receiver(receiver) => {
+                let Some(self_type) = self_type else {
+                    return Err(format_err!(
+                        @receiver, "Coastal does not support 'self' outside impl blocks"
+                    ));
+                };
+                // C has no 'self', so the receiver is named after its type.
+                let mut name = c_identifier(&self_type.to_string().to_case(Case::Snake));
+                if names.contains(&name) {
+                    name.push_str("_self");
+                }
+                let options = arg_options(&mut receiver.attrs)?;
+                arguments.push(Argument {
+                    name,
+                    ty: type_string(&receiver.ty),
+                    options,
+                    doc: take_doc_comment(&mut receiver.attrs),
+                });
             }
-            FnArg::Typed(PatType { pat, ty, .. }) => {
+            FnArg::Typed(PatType { attrs, pat, ty, .. }) => {
                 let name = match pat.as_ref() {
                     Pat::Ident(pat_ident) => pat_ident.ident.to_string(),
                     token => {
@@ -57,9 +525,11 @@ fn handle_fn(item_fn: ItemFn) -> Result<(), Error> {
                     }
                 };
                 match ty.as_ref() {
-                    Type::ImplTrait(TypeImplTrait { impl_token, .. }) => {
+                    Type::ImplTrait(TypeImplTrait { impl_token, bounds })
+                        if !is_closure(bounds) =>
+                    {
                         return Err(format_err!(
-                            @impl_token, "Coastal does not support 'impl' arguments"
+                            @impl_token, "Coastal only supports 'impl Fn' arguments"
                         ));
                     }
                     Type::Infer(t) => {
@@ -77,58 +547,685 @@ fn handle_fn(item_fn: ItemFn) -> Result<(), Error> {
                             format_err!(@t, "Coastal does not support the '!' argument type"),
                         )
                     }
-                    Type::TraitObject(t) => {
-                        return Err(format_err!(@t, "Coastal does not support 'dyn' arguments"))
+                    Type::TraitObject(t) if !is_closure(&t.bounds) => {
+                        return Err(format_err!(@t, "Coastal only supports 'dyn Fn' arguments"))
                     }
                     _ => (),
                 }
-                arguments.push((name, ty.to_token_stream().to_string()));
+                arguments.push(Argument {
+                    name,
+                    ty: type_string(ty.as_ref()),
+                    options: arg_options(attrs)?,
+                    doc: take_doc_comment(attrs),
+                });
             }
         }
     }
-    Function {
-        name: item_fn.sig.ident.to_string(),
-        return_type: item_fn.sig.output.to_token_stream().to_string(),
+    let return_type = match (&sig.output, self_type) {
+        (output, Some(self_type)) => replace_self(output.to_token_stream(), self_type),
+        (output, None) => output.to_token_stream(),
+    };
+    Ok(Function {
+        name: sig.ident.to_string(),
+        return_type: return_type.to_string(),
         arguments,
+        self_type: self_type.map(Ident::to_string),
+        panic: None,
+        error_value: None,
+        is_unsafe: sig.unsafety.is_some(),
+        return_options: Default::default(),
+        options: Default::default(),
+        instantiations: Vec::new(),
+        instantiation: None,
+    })
+}
+
+/// Returns the instantiations of a generic function, checking that each gives an argument for
+/// every type and const parameter.
+fn instantiations<'a>(
+    sig: &Signature,
+    instantiate: impl IntoIterator<Item = &'a InstantiateArgs>,
+) -> Result<Vec<Instantiation>, Error> {
+    let params: Vec<&Ident> = sig
+        .generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(param) => Some(&param.ident),
+            GenericParam::Const(param) => Some(&param.ident),
+            GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+    let mut instantiations: Vec<Instantiation> = Vec::new();
+    for instantiate in instantiate {
+        if params.is_empty() {
+            return Err(format_err!(
+                @&instantiate.path, "only generic functions can be instantiated"
+            ));
+        }
+        let Some(suffix) = &instantiate.suffix else {
+            return Err(format_err!(
+                @&instantiate.path, "each instantiation needs a 'suffix' for its C name"
+            ));
+        };
+        let valid = |c: char| c.is_ascii_alphanumeric() || c == '_';
+        if suffix.value().is_empty() || !suffix.value().chars().all(valid) {
+            return Err(format_err!(
+                @suffix, "'{}' is not a valid suffix for a C name", suffix.value()
+            ));
+        }
+        if instantiations.iter().any(|i| i.suffix == suffix.value()) {
+            return Err(format_err!(
+                @suffix, "the suffix '{}' is already used", suffix.value()
+            ));
+        }
+        if let Some((param, _)) = instantiate
+            .arguments
+            .iter()
+            .find(|(param, _)| !params.contains(&param))
+        {
+            return Err(format_err!(
+                @param, "'{param}' is not a generic parameter of this function"
+            ));
+        }
+        let arguments = params
+            .iter()
+            .map(|param| {
+                let mut arguments = instantiate.arguments.iter().filter(|(p, _)| p == *param);
+                match (arguments.next(), arguments.next()) {
+                    (Some((_, argument)), None) => {
+                        Ok((param.to_string(), argument.to_token_stream().to_string()))
+                    }
+                    (Some(_), Some((duplicate, _))) => Err(format_err!(
+                        @duplicate, "'{param}' is given more than once"
+                    )),
+                    (None, _) => Err(format_err!(
+                        @&instantiate.path, "missing an argument for '{param}'"
+                    )),
+                }
+            })
+            .collect::<Result<_, Error>>()?;
+        instantiations.push(Instantiation {
+            arguments,
+            suffix: suffix.value(),
+        });
+    }
+    if instantiations.is_empty() && !params.is_empty() {
+        return Err(format_err!(
+            @&sig.generics,
+            "Coastal can only export generic functions with \
+             #[coast(instantiate(T = ..., suffix = \"...\"))]"
+        ));
+    }
+    // Rust doesn't allow explicit generic arguments if there are `impl Trait` arguments.
+    if !instantiations.is_empty() {
+        if let Some(impl_trait) = sig.inputs.iter().find_map(|arg| match arg {
+            FnArg::Typed(PatType { ty, .. }) => match ty.as_ref() {
+                Type::ImplTrait(impl_trait) => Some(impl_trait),
+                _ => None,
+            },
+            FnArg::Receiver(_) => None,
+        }) {
+            return Err(format_err!(
+                @impl_trait,
+                "Coastal can't instantiate generic functions with 'impl' arguments, use 'dyn' instead"
+            ));
+        }
+    }
+    Ok(instantiations)
+}
+
+/// Saves the doc comment of a function, moving any `# Arguments` section to the arguments.
+fn document_function(function: &mut Function, attrs: &[Attribute]) {
+    let Some(doc) = doc_comment(attrs) else {
+        return;
+    };
+    let (doc, arguments) = split_arguments(&doc);
+    for (name, description) in arguments {
+        // A doc comment on the argument itself takes precedence.
+        if let Some(argument) = function
+            .arguments
+            .iter_mut()
+            .find(|arg| arg.name == name && arg.doc.is_none())
+        {
+            argument.doc = Some(description);
+        }
+    }
+    function.options.doc = (!doc.is_empty()).then_some(doc);
+}
+
+/// Returns the Markdown of the `///` and `#[doc = "..."]` comments in `attrs`, if any.
+fn doc_comment(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) if meta.path.is_ident("doc") => match &meta.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(doc), ..
+                }) => Some(doc.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .flat_map(|doc| {
+            // `/// text` is `#[doc = " text"]`.
+            doc.split('\n')
+                .map(|line| line.strip_prefix(' ').unwrap_or(line).trim_end().to_owned())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    let doc = lines.join("\n").trim().to_owned();
+    (!doc.is_empty()).then_some(doc)
+}
+
+/// Returns and removes the doc comments of an argument, since Rust doesn't allow them.
+fn take_doc_comment(attrs: &mut Vec<Attribute>) -> Option<String> {
+    let doc = doc_comment(attrs);
+    attrs.retain(|attr| !attr.path().is_ident("doc"));
+    doc
+}
+
+/// Returns an error if a function has raw pointer arguments or a raw pointer return type, unless
+/// it's an `unsafe fn` or marked `#[coast(unsafe)]`, since C can pass any pointer.
+fn check_raw_pointers(sig: &Signature, allow_unsafe: bool) -> Result<(), Error> {
+    if allow_unsafe || sig.unsafety.is_some() {
+        return Ok(());
+    }
+    let output = match &sig.output {
+        ReturnType::Type(_, ty) => Some(ty.as_ref()),
+        ReturnType::Default => None,
+    };
+    let types = sig
+        .inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            FnArg::Typed(PatType { ty, .. }) => Some(ty.as_ref()),
+            FnArg::Receiver(_) => None,
+        })
+        .chain(output);
+    for ty in types {
+        if let Some(ptr) = find_raw_pointer(ty) {
+            return Err(format_err!(
+                @ptr,
+                "Coastal only allows raw pointers in 'unsafe fn' or with #[coast(unsafe)]"
+            ));
+        }
     }
-    .save_state(&item_fn.sig.ident)?;
     Ok(())
 }
 
-fn handle_const(item_const: ItemConst) -> Result<(), Error> {
-    let Expr::Lit(ExprLit { lit, .. }) = item_const.expr.as_ref() else {
+/// Returns the first raw pointer in a type, including in generic arguments like `Option<*mut T>`.
+fn find_raw_pointer(ty: &Type) -> Option<&TypePtr> {
+    match ty {
+        Type::Ptr(ptr) => Some(ptr),
+        Type::Array(TypeArray { elem, .. })
+        | Type::Group(TypeGroup { elem, .. })
+        | Type::Paren(TypeParen { elem, .. })
+        | Type::Reference(TypeReference { elem, .. })
+        | Type::Slice(TypeSlice { elem, .. }) => find_raw_pointer(elem),
+        Type::Path(TypePath { path, .. }) => path.segments.iter().find_map(|segment| {
+            let PathArguments::AngleBracketed(args) = &segment.arguments else {
+                return None;
+            };
+            args.args.iter().find_map(|arg| match arg {
+                GenericArgument::Type(ty) => find_raw_pointer(ty),
+                _ => None,
+            })
+        }),
+        Type::Tuple(tuple) => tuple.elems.iter().find_map(find_raw_pointer),
+        _ => None,
+    }
+}
+
+/// Returns true if the bounds of an `impl` or `dyn` type are a closure like `Fn(T) -> U`.
+fn is_closure(bounds: &Punctuated<TypeParamBound, Token![+]>) -> bool {
+    bounds.iter().any(|bound| match bound {
+        TypeParamBound::Trait(TraitBound { path, .. }) => path
+            .segments
+            .last()
+            .is_some_and(|s| matches!(s.arguments, PathArguments::Parenthesized(_))),
+        _ => false,
+    })
+}
+
+/// Parses and removes the `#[coast(...)]` attributes of an argument.
+fn arg_options(attrs: &mut Vec<Attribute>) -> Result<ArgOptions, Error> {
+    let mut options = ArgOptions::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("coast")) {
+        let parser = syn::meta::parser(|meta| {
+            if meta.path.is_ident("len") {
+                options.len = true;
+                Ok(())
+            } else if meta.path.is_ident("nullable") {
+                options.nullable = true;
+                Ok(())
+            } else if meta.path.is_ident("invalid_utf8") {
+                options.invalid_utf8 = Some(utf8_policy(&meta.value()?.parse()?)?);
+                Ok(())
+            } else if meta.path.is_ident("c_type") {
+                let c_type: LitStr = meta.value()?.parse()?;
+                options.c_type = Some(c_type.value());
+                Ok(())
+            } else {
+                Err(meta.error("unknown Coastal argument option"))
+            }
+        });
+        attr.parse_args_with(parser)?;
+    }
+    attrs.retain(|attr| !attr.path().is_ident("coast"));
+    Ok(options)
+}
+
+/// Replaces `Self` with the name of the type, since the wrappers aren't in the impl block.
+fn replace_self(tokens: TokenStream, self_type: &Ident) -> TokenStream {
+    replace_idents(tokens, &|ident| {
+        (ident == "Self").then(|| self_type.to_token_stream())
+    })
+}
+
+fn handle_struct(item_struct: ItemStruct, args: &CoastArgs) -> Result<(), Error> {
+    if item_struct.generics.lt_token.is_some() {
+        return Err(format_err!(
+            @item_struct.generics, "Coastal does not support generic structs"
+        ));
+    }
+    if args.opaque.is_some() {
+        Struct {
+            name: item_struct.ident.to_string(),
+            fields: Vec::new(),
+            opaque: true,
+            doc: doc_comment(&item_struct.attrs),
+        }
+        .save_state(&item_struct.ident)?;
+        return Ok(());
+    }
+    if !has_repr_c(&item_struct.attrs)? {
+        return Err(format_err!(
+            @item_struct.ident, "Coastal structs must be #[repr(C)]"
+        ));
+    }
+    let Fields::Named(fields) = &item_struct.fields else {
+        return Err(format_err!(
+            @item_struct.fields, "Coastal structs must have named fields"
+        ));
+    };
+    let fields = fields
+        .named
+        .iter()
+        .map(|field| {
+            let name = field.ident.as_ref().expect("named field").to_string();
+            (name, field.ty.to_token_stream().to_string())
+        })
+        .collect();
+    Struct {
+        name: item_struct.ident.to_string(),
+        fields,
+        opaque: false,
+        doc: doc_comment(&item_struct.attrs),
+    }
+    .save_state(&item_struct.ident)?;
+    Ok(())
+}
+
+/// Saves an enum, returning the `coastal::IntoCError` impl if it's `#[coast(error)]`.
+fn handle_enum(item_enum: ItemEnum, args: &CoastArgs) -> Result<TokenStream, Error> {
+    if item_enum.generics.lt_token.is_some() {
+        return Err(format_err!(
+            @item_enum.generics, "Coastal does not support generic enums"
+        ));
+    }
+    let mut variants = Vec::new();
+    let mut default = None;
+    let mut next = 0i64;
+    for variant in &item_enum.variants {
+        let value = match &variant.discriminant {
+            Some((_, expr)) => enum_discriminant(expr)?,
+            None => next,
+        };
+        // C enum constants are always `int`.
+        if i32::try_from(value).is_err() {
+            return Err(format_err!(
+                @variant, "Coastal enum values must fit in a C 'int'"
+            ));
+        }
+        if variant.attrs.iter().any(|a| a.path().is_ident("default")) {
+            default = Some(variant.ident.to_string());
+        }
+        let fields = variant
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let name = match &field.ident {
+                    Some(ident) => ident.to_string(),
+                    None => index.to_string(),
+                };
+                (name, field.ty.to_token_stream().to_string())
+            })
+            .collect();
+        variants.push(Variant {
+            name: variant.ident.to_string(),
+            value,
+            fields,
+            doc: doc_comment(&variant.attrs),
+        });
+        next = value + 1;
+    }
+    let invalid = args.invalid.as_ref().map(invalid_enum_policy).transpose()?;
+    if invalid == Some(InvalidEnumPolicy::Default) && default.is_none() {
+        return Err(format_err!(
+            @item_enum.ident, "Coastal enums need a #[default] variant for invalid = \"default\""
+        ));
+    }
+    let mut error_impl = TokenStream::new();
+    if let Some(error) = &args.error {
+        if variants.iter().any(|v| !v.fields.is_empty()) {
+            return Err(format_err!(@error, "Coastal error enums must not have fields"));
+        }
+        if let Some(variant) = variants.iter().find(|v| v.value == 0) {
+            return Err(format_err!(
+                @error, "Coastal error enums can't use 0, which means success, for '{}'", variant.name
+            ));
+        }
+        let name = &item_enum.ident;
+        let arms = variants.iter().map(|v| {
+            let variant = Ident::new(&v.name, Span::call_site());
+            let value = int_literal(v.value);
+            quote! { Self::#variant => #value, }
+        });
+        error_impl = quote! {
+            impl ::coastal::IntoCError for #name {
+                fn error_code(&self) -> std::ffi::c_int {
+                    match self {
+                        #(#arms)*
+                    }
+                }
+            }
+        };
+    }
+    Enum {
+        name: item_enum.ident.to_string(),
+        repr: enum_repr(&item_enum.attrs)?,
+        variants,
+        default,
+        invalid,
+        error: args.error.is_some(),
+        doc: doc_comment(&item_enum.attrs),
+    }
+    .save_state(&item_enum.ident)?;
+    Ok(error_impl)
+}
+
+/// Returns the value of an explicit enum discriminant, which must be an integer literal.
+fn enum_discriminant(expr: &Expr) -> Result<i64, Error> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) => parse_int(lit, false),
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => match expr.as_ref() {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(lit), ..
+            }) => parse_int(lit, true),
+            _ => Err(format_err!(@expr, "Coastal enum values must be integer literals")),
+        },
+        Expr::Group(ExprGroup { expr, .. }) | Expr::Paren(ExprParen { expr, .. }) => {
+            enum_discriminant(expr)
+        }
+        _ => Err(format_err!(@expr, "Coastal enum values must be integer literals")),
+    }
+}
+
+/// Returns the integer type from `#[repr(...)]`, or `None` for `#[repr(C)]` or no `repr`.
+fn enum_repr(attrs: &[Attribute]) -> Result<Option<String>, Error> {
+    const INT_TYPES: &[&str] = &[
+        "i8", "i16", "i32", "i64", "isize", "u8", "u16", "u32", "u64", "usize",
+    ];
+    let mut repr = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            if let Some(ty) = INT_TYPES.iter().find(|ty| meta.path.is_ident(ty)) {
+                repr = Some((*ty).to_owned());
+            } else if meta.path.is_ident("i128") || meta.path.is_ident("u128") {
+                return Err(meta.error("Coastal does not support 128-bit enums"));
+            } else if meta.input.peek(token::Paren) {
+                meta.input.parse::<TokenTree>()?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(repr)
+}
+
+/// Returns true if the attributes include `#[repr(C)]`.
+fn has_repr_c(attrs: &[Attribute]) -> Result<bool, Error> {
+    let mut repr_c = false;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") {
+                repr_c = true;
+            } else if meta.input.peek(token::Paren) {
+                // Skip arguments like `align(8)`.
+                meta.input.parse::<TokenTree>()?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(repr_c)
+}
+
+fn handle_const(item_const: ItemConst, args: &CoastArgs) -> Result<(), Error> {
+    let mut options = ItemOptions {
+        doc: doc_comment(&item_const.attrs),
+        ..Default::default()
+    };
+    args.apply_item(&mut options)?;
+    let Some(ty) = const_type_name(&item_const.ty) else {
         return Err(format_err!(
             @item_const.ty,
-            "Coastal constants must have a literal value"
+            "Coastal constants can only be bool, char, int, float, bytes, or string types"
         ));
     };
-    let value = match (&item_const.ty.to_token_stream().to_string()[..], lit) {
-        ("c_char", Lit::Byte(l)) => ConstantValue::CChar(l.value()),
-        ("i8", Lit::Int(l)) => ConstantValue::I8(l.base10_parse()?),
-        ("i16", Lit::Int(l)) => ConstantValue::I8(l.base10_parse()?),
-        ("i32", Lit::Int(l)) => ConstantValue::I8(l.base10_parse()?),
-        ("i64", Lit::Int(l)) => ConstantValue::I8(l.base10_parse()?),
-        ("u8", Lit::Int(l)) => ConstantValue::I8(l.base10_parse()?),
-        ("u16", Lit::Int(l)) => ConstantValue::I8(l.base10_parse()?),
-        ("u32", Lit::Int(l)) => ConstantValue::I8(l.base10_parse()?),
-        ("u64", Lit::Int(l)) => ConstantValue::I8(l.base10_parse()?),
-        ("isize", Lit::Int(l)) => ConstantValue::I8(l.base10_parse()?),
-        ("usize", Lit::Int(l)) => ConstantValue::I8(l.base10_parse()?),
-        ("f32", Lit::Float(l)) => ConstantValue::F32(l.base10_parse()?),
-        ("f64", Lit::Float(l)) => ConstantValue::F32(l.base10_parse()?),
-        ("& str", Lit::Str(l)) => ConstantValue::Str(l.value()),
-        ("& [u8]", Lit::ByteStr(l)) => ConstantValue::Bytes(l.value()),
+    let literal = match item_const.expr.as_ref() {
+        Expr::Lit(ExprLit { lit, .. }) => Some((false, lit)),
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => match expr.as_ref() {
+            Expr::Lit(ExprLit { lit, .. }) => Some((true, lit)),
+            _ => None,
+        },
+        _ => None,
+    };
+    let Some((negative, lit)) = literal else {
+        if !matches!(&ty[..], "bool" | "f32" | "f64") && int_type(&ty).is_none() {
+            return Err(format_err!(
+                @item_const.expr,
+                "Coastal constants of this type must have a literal value"
+            ));
+        }
+        Constant {
+            name: item_const.ident.to_string(),
+            value: ConstantValue::Expr(ty, parse_const_expr(&item_const.expr)?),
+            options,
+        }
+        .save_state(&item_const.ident)?;
+        return Ok(());
+    };
+    let value = match (&ty[..], lit) {
+        ("bool", Lit::Bool(l)) if !negative => ConstantValue::Bool(l.value),
+        ("c_char", Lit::Byte(l)) if !negative => ConstantValue::CChar(l.value()),
+        ("c_char", Lit::Int(l)) => {
+            // c_char is signed on some platforms and unsigned on others.
+            let value: i16 = parse_int(l, negative)?;
+            if !(-128..=255).contains(&value) {
+                return Err(format_err!(@l, "value is out of range for this type"));
+            }
+            ConstantValue::CChar(value as u8)
+        }
+        ("char", Lit::Char(l)) if !negative => ConstantValue::Char(l.value()),
+        ("i8", Lit::Int(l)) => ConstantValue::I8(parse_int(l, negative)?),
+        ("i16", Lit::Int(l)) => ConstantValue::I16(parse_int(l, negative)?),
+        ("i32", Lit::Int(l)) => ConstantValue::I32(parse_int(l, negative)?),
+        ("i64", Lit::Int(l)) => ConstantValue::I64(parse_int(l, negative)?),
+        ("i128", Lit::Int(l)) => ConstantValue::I128(parse_int(l, negative)?),
+        ("isize", Lit::Int(l)) => ConstantValue::Isize(parse_int(l, negative)?),
+        ("u8", Lit::Byte(l)) if !negative => ConstantValue::U8(l.value()),
+        ("u8", Lit::Int(l)) => ConstantValue::U8(parse_int(l, negative)?),
+        ("u16", Lit::Int(l)) => ConstantValue::U16(parse_int(l, negative)?),
+        ("u32", Lit::Int(l)) => ConstantValue::U32(parse_int(l, negative)?),
+        ("u64", Lit::Int(l)) => ConstantValue::U64(parse_int(l, negative)?),
+        ("u128", Lit::Int(l)) => ConstantValue::U128(parse_int(l, negative)?),
+        ("usize", Lit::Int(l)) => ConstantValue::Usize(parse_int(l, negative)?),
+        ("f32", Lit::Float(l)) => ConstantValue::F32(parse_float(l, l.base10_digits(), negative)?),
+        ("f32", Lit::Int(l)) => ConstantValue::F32(parse_float(l, l.base10_digits(), negative)?),
+        ("f64", Lit::Float(l)) => ConstantValue::F64(parse_float(l, l.base10_digits(), negative)?),
+        ("f64", Lit::Int(l)) => ConstantValue::F64(parse_float(l, l.base10_digits(), negative)?),
+        ("&str", Lit::Str(l)) if !negative => ConstantValue::Str(l.value()),
+        ("&[u8]", Lit::ByteStr(l)) if !negative => ConstantValue::Bytes(l.value()),
         _ => {
             return Err(format_err!(
-                @item_const.ty,
-                "Coastal constants can only be c_char, int, float, bytes, or string types"
+                @item_const.expr,
+                "Coastal can't convert this value to '{}'",
+                item_const.ty.to_token_stream()
             ))
         }
     };
     Constant {
         name: item_const.ident.to_string(),
         value,
+        options,
     }
     .save_state(&item_const.ident)?;
     Ok(())
 }
+
+/// Parses the expression of a constant that isn't a simple literal.
+fn parse_const_expr(expr: &Expr) -> Result<ConstExpr, Error> {
+    Ok(match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(l), ..
+        }) => ConstExpr::Int(l.base10_parse()?),
+        Expr::Lit(ExprLit {
+            lit: Lit::Float(l), ..
+        }) => ConstExpr::Float(l.base10_parse()?),
+        Expr::Lit(ExprLit {
+            lit: Lit::Bool(l), ..
+        }) => ConstExpr::Bool(l.value),
+        Expr::Path(ExprPath {
+            qself: None, path, ..
+        }) if path.get_ident().is_some() => ConstExpr::Const(path.segments[0].ident.to_string()),
+        Expr::Paren(ExprParen { expr, .. }) | Expr::Group(ExprGroup { expr, .. }) => {
+            parse_const_expr(expr)?
+        }
+        Expr::Unary(ExprUnary { op, expr, .. }) => {
+            let op = match op {
+                UnOp::Neg(_) => UnaryOp::Neg,
+                UnOp::Not(_) => UnaryOp::Not,
+                _ => return Err(format_err!(@op, "Coastal doesn't support this operator")),
+            };
+            ConstExpr::Unary(op, Box::new(parse_const_expr(expr)?))
+        }
+        Expr::Binary(ExprBinary {
+            left, op, right, ..
+        }) => {
+            let op = match op {
+                BinOp::Add(_) => BinaryOp::Add,
+                BinOp::Sub(_) => BinaryOp::Sub,
+                BinOp::Mul(_) => BinaryOp::Mul,
+                BinOp::Div(_) => BinaryOp::Div,
+                BinOp::Rem(_) => BinaryOp::Rem,
+                BinOp::BitAnd(_) => BinaryOp::BitAnd,
+                BinOp::BitOr(_) => BinaryOp::BitOr,
+                BinOp::BitXor(_) => BinaryOp::BitXor,
+                BinOp::Shl(_) => BinaryOp::Shl,
+                BinOp::Shr(_) => BinaryOp::Shr,
+                BinOp::And(_) => BinaryOp::And,
+                BinOp::Or(_) => BinaryOp::Or,
+                _ => return Err(format_err!(@op, "Coastal doesn't support this operator")),
+            };
+            ConstExpr::Binary(
+                op,
+                Box::new(parse_const_expr(left)?),
+                Box::new(parse_const_expr(right)?),
+            )
+        }
+        Expr::Cast(ExprCast { expr, ty, .. }) => match const_type_name(ty) {
+            Some(name)
+                if name == "bool"
+                    || name == "f32"
+                    || name == "f64"
+                    || int_type(&name).is_some() =>
+            {
+                ConstExpr::Cast(Box::new(parse_const_expr(expr)?), name)
+            }
+            _ => return Err(format_err!(@ty, "Coastal can only cast constants to numeric types")),
+        },
+        _ => {
+            return Err(format_err!(
+                @expr,
+                "Coastal constants must be literals, other constants, or simple expressions"
+            ))
+        }
+    })
+}
+
+/// Returns a normalised name for a constant's type, such as `u32`, `c_char` or `&str`.
+fn const_type_name(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Group(TypeGroup { elem, .. }) | Type::Paren(TypeParen { elem, .. }) => {
+            const_type_name(elem)
+        }
+        Type::Path(TypePath { qself: None, path }) => Some(path.segments.last()?.ident.to_string()),
+        Type::Reference(TypeReference {
+            mutability: None,
+            elem,
+            ..
+        }) => match elem.as_ref() {
+            Type::Path(TypePath { qself: None, path }) if path.is_ident("str") => {
+                Some("&str".to_owned())
+            }
+            Type::Slice(TypeSlice { elem, .. }) => match elem.as_ref() {
+                Type::Path(TypePath { qself: None, path }) if path.is_ident("u8") => {
+                    Some("&[u8]".to_owned())
+                }
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Parses an integer literal with an optional leading minus sign, checking its range.
+fn parse_int<T: TryFrom<i128> + TryFrom<u128>>(lit: &LitInt, negative: bool) -> Result<T, Error> {
+    let out_of_range = || format_err!(@lit, "value is out of range for this type");
+    let magnitude: u128 = lit.base10_parse().map_err(|_| out_of_range())?;
+    if negative {
+        let value = 0i128
+            .checked_sub_unsigned(magnitude)
+            .ok_or_else(out_of_range)?;
+        T::try_from(value).map_err(|_| out_of_range())
+    } else {
+        T::try_from(magnitude).map_err(|_| out_of_range())
+    }
+}
+
+/// Parses a float literal with an optional leading minus sign, rejecting infinite values.
+fn parse_float<T: FromStr + Neg<Output = T> + Into<f64> + Copy>(
+    lit: impl ToTokens,
+    digits: &str,
+    negative: bool,
+) -> Result<T, Error> {
+    let value: T = digits
+        .parse()
+        .map_err(|_| format_err!(@&lit, "invalid float literal"))?;
+    if !value.into().is_finite() {
+        return Err(format_err!(@lit, "value is out of range for this type"));
+    }
+    Ok(if negative { -value } else { value })
+}
@@ -1,71 +1,246 @@
 use std::str::FromStr;
 
-use proc_macro2::{Ident, Span, TokenStream};
+use convert_case::{Case, Casing};
+use proc_macro2::{Group, Ident, Span, TokenStream, TokenTree};
 use quote::{quote, ToTokens};
 use syn::{
-    Abi, BareFnArg, Expr, Lifetime, Path, ReturnType, Type, TypeArray, TypeBareFn, TypeGroup,
-    TypeParen, TypePath, TypePtr, TypeReference,
+    punctuated::Punctuated, Expr, ExprGroup, ExprLit, ExprParen, ExprPath, GenericArgument,
+    Lifetime, Lit, ParenthesizedGenericArguments, Path, PathArguments, ReturnType, Token,
+    TraitBound, TraitBoundModifier, Type, TypeArray, TypeBareFn, TypeGroup, TypeImplTrait,
+    TypeParamBound, TypeParen, TypePath, TypePtr, TypeReference, TypeSlice, TypeTraitObject,
+    TypeTuple,
 };
 
-use crate::api::{ConvertArg, ConvertReturn};
+use crate::api::{
+    int_literal, type_name, ArgOptions, ConvertArg, ConvertReturn, Enum, InvalidEnumPolicy,
+    Library, NulPolicy, ReturnOptions, SliceType, Utf8Policy,
+};
+
+/// Types passed between C and Rust by value without conversion: `(name, path, C type)`.
+const NO_CONVERSION: &[(&str, &str, &str)] = &[
+    ("bool", "bool", "bool"),
+    ("i8", "i8", "int8_t"),
+    ("i16", "i16", "int16_t"),
+    ("i32", "i32", "int32_t"),
+    ("i64", "i64", "int64_t"),
+    ("u8", "u8", "uint8_t"),
+    ("u16", "u16", "uint16_t"),
+    ("u32", "u32", "uint32_t"),
+    ("u64", "u64", "uint64_t"),
+    ("usize", "usize", "size_t"),
+    ("isize", "isize", "intptr_t"),
+    ("f32", "f32", "float"),
+    ("f64", "f64", "double"),
+    ("c_char", "std::ffi::c_char", "char"),
+    ("c_uchar", "std::ffi::c_uchar", "unsigned char"),
+    ("c_schar", "std::ffi::c_schar", "signed char"),
+];
+
+/// Non-zero integers, which are passed as their integer type: `(name, integer type, C type)`.
+/// `Option`s of them are zero for `None`, which the header notes for arguments.
+const NON_ZERO: &[(&str, &str, &str)] = &[
+    ("NonZeroI8", "i8", "int8_t"),
+    ("NonZeroI16", "i16", "int16_t"),
+    ("NonZeroI32", "i32", "int32_t"),
+    ("NonZeroI64", "i64", "int64_t"),
+    ("NonZeroU8", "u8", "uint8_t"),
+    ("NonZeroU16", "u16", "uint16_t"),
+    ("NonZeroU32", "u32", "uint32_t"),
+    ("NonZeroU64", "u64", "uint64_t"),
+    ("NonZeroIsize", "isize", "intptr_t"),
+    ("NonZeroUsize", "usize", "size_t"),
+];
+
+/// C and C++ keywords that are valid Rust identifiers.
+const C_KEYWORDS: &[&str] = &[
+    "auto",
+    "bool",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "default",
+    "delete",
+    "double",
+    "explicit",
+    "float",
+    "friend",
+    "goto",
+    "inline",
+    "int",
+    "long",
+    "namespace",
+    "new",
+    "operator",
+    "private",
+    "protected",
+    "public",
+    "register",
+    "restrict",
+    "short",
+    "signed",
+    "sizeof",
+    "switch",
+    "template",
+    "this",
+    "throw",
+    "typedef",
+    "typename",
+    "union",
+    "unsigned",
+    "virtual",
+    "void",
+    "volatile",
+];
+
+/// Rust keywords, which can't name the fields and arguments that Coastal generates either, since
+/// the wrappers use the same names.
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "static", "struct", "super", "trait", "true", "try", "type", "typeof", "unsafe",
+    "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Returns `name` with a trailing underscore if it's a C, C++ or Rust keyword.
+pub fn c_identifier(name: &str) -> String {
+    if C_KEYWORDS.contains(&name) || RUST_KEYWORDS.contains(&name) {
+        format!("{name}_")
+    } else {
+        name.to_owned()
+    }
+}
+
+/// Replaces the identifiers in `tokens` that `replace` returns tokens for, including inside
+/// groups like `Vec<(T, U)>`.
+pub fn replace_idents(
+    tokens: TokenStream,
+    replace: &impl Fn(&Ident) -> Option<TokenStream>,
+) -> TokenStream {
+    let mut replaced = TokenStream::new();
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => match replace(&ident) {
+                Some(tokens) => replaced.extend(tokens),
+                None => replaced.extend([TokenTree::Ident(ident)]),
+            },
+            TokenTree::Group(group) => {
+                let mut new_group =
+                    Group::new(group.delimiter(), replace_idents(group.stream(), replace));
+                new_group.set_span(group.span());
+                replaced.extend([TokenTree::Group(new_group)]);
+            }
+            token => replaced.extend([token]),
+        }
+    }
+    replaced
+}
+
+/// Returns the C type for a type that can be passed by value without conversion.
+pub fn c_value_type(lib: &Library, ty: &Type) -> Option<String> {
+    if let Some(s) = lib.find_struct(ty) {
+        return (!s.opaque).then(|| s.c_name(lib));
+    }
+    let name = type_name(ty)?;
+    NO_CONVERSION
+        .iter()
+        .find(|(n, _, _)| n == &name)
+        .map(|(_, _, c)| (*c).to_owned())
+}
 
 /// Returns the code to convert an argument from C to Rust.
-pub fn convert_builtin_arg(name: &Ident, arg_type: &Type) -> Option<ConvertArg> {
+pub fn convert_builtin_arg(
+    lib: &Library,
+    name: &Ident,
+    arg_type: &Type,
+    options: &ArgOptions,
+) -> Option<ConvertArg> {
+    if let Some(arg) = convert_string_arg(lib, name, arg_type, options) {
+        return Some(arg);
+    }
+    if options.len {
+        return None; // Only strings can be passed with a length.
+    }
+    if let Type::Path(TypePath { qself: None, path }) = arg_type {
+        if let Some(inner) = generic_arg(path, "Option") {
+            return convert_option_arg(lib, name, inner, options);
+        }
+    }
+    if options.nullable {
+        return None; // Only options can be nullable.
+    }
     match arg_type {
-        Type::Array(TypeArray { elem, len, .. }) => convert_array_arg(name, elem, len),
+        Type::Array(array) => convert_array_arg(lib, name, array),
         Type::BareFn(TypeBareFn {
             lifetimes: Some(_), ..
         }) => None, // function with lifetime
         Type::BareFn(TypeBareFn {
             variadic: Some(_), ..
         }) => None, // variadic function
-        Type::BareFn(TypeBareFn {
-            unsafety,
-            abi,
-            inputs,
-            output,
-            ..
-        }) => convert_fn_ptr(name, inputs, output, abi.as_ref(), unsafety.is_some()),
+        Type::BareFn(bare_fn) => convert_fn_ptr(lib, name, bare_fn, false, options),
         Type::Group(TypeGroup { elem, .. }) | Type::Paren(TypeParen { elem, .. }) => {
-            convert_builtin_arg(name, elem)
+            convert_builtin_arg(lib, name, elem, options)
         }
         Type::Path(TypePath { qself: Some(_), .. }) => None, // <T as Trait>::U
-        Type::Path(TypePath { path, .. }) => convert_path_arg(name, path),
-        Type::Ptr(TypePtr {
-            mutability, elem, ..
-        }) => convert_ptr_arg(name, elem, mutability.is_some()),
+        Type::Path(TypePath { path, .. }) => convert_path_arg(lib, name, arg_type, path, options),
+        Type::Ptr(ptr) => convert_ptr_arg(lib, name, ptr),
         Type::Reference(TypeReference {
             lifetime,
             mutability,
             elem,
             ..
-        }) => convert_ref_arg(name, lifetime.as_ref(), elem, mutability.is_some()),
-        Type::Slice(_) => None,       // bare slice [T]
-        Type::ImplTrait(_) => None,   // impl Trait
+        }) => convert_ref_arg(
+            lib,
+            name,
+            lifetime.as_ref(),
+            elem,
+            mutability.is_some(),
+            options,
+        ),
+        Type::Slice(_) => None, // bare slice [T]
+        Type::ImplTrait(TypeImplTrait { bounds, .. }) => {
+            convert_closure_arg(lib, name, bounds, options, |closure| closure)
+        }
         Type::TraitObject(_) => None, // dyn Trait
         Type::Tuple(_) => None,       // (T, U, ...)
         _ => None,
     }
 }
 
-pub fn convert_builtin_return(return_type: &ReturnType) -> Option<ConvertReturn> {
+pub fn convert_builtin_return(
+    lib: &Library,
+    return_type: &ReturnType,
+    options: &ReturnOptions,
+) -> Option<ConvertReturn> {
     match return_type {
         ReturnType::Default => Some(ConvertReturn {
             before: quote! {},
             after: quote! { ; },
             return_type: quote! { () },
             c_type: "void".to_owned(),
+            ..Default::default()
         }),
-        ReturnType::Type(_, ty) => convert_builtin_result_type(ty.as_ref()),
+        ReturnType::Type(_, ty) if matches!(ty.as_ref(), Type::Tuple(t) if t.elems.is_empty()) => {
+            convert_builtin_return(lib, &ReturnType::Default, options)
+        }
+        ReturnType::Type(_, ty) => convert_builtin_result_type(lib, ty.as_ref(), options),
     }
 }
 
-fn convert_builtin_result_type(ty: &Type) -> Option<ConvertReturn> {
+fn convert_builtin_result_type(
+    lib: &Library,
+    ty: &Type,
+    options: &ReturnOptions,
+) -> Option<ConvertReturn> {
+    if let Some(ret) = convert_string_return(lib, ty) {
+        return Some(ret);
+    }
     match ty {
         Type::Array(_) => None,  // can't return [T; N]
         Type::BareFn(_) => None, // can't return fn(...) -> T
         Type::Group(TypeGroup { elem, .. }) | Type::Paren(TypeParen { elem, .. }) => {
-            convert_builtin_result_type(elem)
+            convert_builtin_result_type(lib, elem, options)
         }
         Type::ImplTrait(_) => None, // impl T
         Type::Never(_) => Some(ConvertReturn {
@@ -73,64 +248,587 @@ fn convert_builtin_result_type(ty: &Type) -> Option<ConvertReturn> {
             after: quote! { ; },
             return_type: quote! { ! },
             c_type: "void".to_owned(),
+            ..Default::default()
         }), // no return
         Type::Path(TypePath { qself: Some(_), .. }) => None,
-        Type::Path(TypePath { path, .. }) => convert_path_return(path), // return by value
-        Type::Ptr(_) => todo!("return pointer"),
-        Type::Reference(_) => todo!("return ref"), // return pointer
-        Type::Slice(_) => None,                    // [T]
-        Type::TraitObject(_) => None,              // dyn Trait
-        Type::Tuple(_) => None,                    // (T, U, ...)
+        Type::Path(TypePath { path, .. }) => convert_path_return(lib, ty, path, options), // return by value
+        Type::Ptr(ptr) => Some(ConvertReturn {
+            before: quote! {},
+            after: quote! {},
+            return_type: quote! { #ptr },
+            c_type: format!("{} /* nullable */", c_pointer_type(lib, ptr)?),
+            error_value: Some(if ptr.mutability.is_some() {
+                quote! { std::ptr::null_mut() }
+            } else {
+                quote! { std::ptr::null() }
+            }),
+            error_doc: Some("NULL".to_owned()),
+            ..Default::default()
+        }),
+        Type::Reference(reference) => convert_ref_return(lib, reference),
+        Type::Slice(_) => None,       // [T]
+        Type::TraitObject(_) => None, // dyn Trait
+        Type::Tuple(tuple) => convert_tuple_return(lib, tuple, options),
+        _ => None,
+    }
+}
+
+/// Converts `&T` and `&mut T` to `const T*` and `T*`, which are only null after a panic.
+fn convert_ref_return(lib: &Library, reference: &TypeReference) -> Option<ConvertReturn> {
+    let elem = &reference.elem;
+    let c_type = c_pointee_type(lib, elem)?;
+    Some(if reference.mutability.is_some() {
+        ConvertReturn {
+            before: quote! { let ret = },
+            after: quote! { ; ret as *mut #elem },
+            return_type: quote! { *mut #elem },
+            c_type: format!("{c_type}*"),
+            error_value: Some(quote! { std::ptr::null_mut() }),
+            error_doc: Some("NULL".to_owned()),
+            ..Default::default()
+        }
+    } else {
+        ConvertReturn {
+            before: quote! { let ret = },
+            after: quote! { ; ret as *const #elem },
+            return_type: quote! { *const #elem },
+            c_type: format!("const {c_type}*"),
+            error_value: Some(quote! { std::ptr::null() }),
+            error_doc: Some("NULL".to_owned()),
+            ..Default::default()
+        }
+    })
+}
+
+/// Converts `[T; N]` by value from a pointer to its elements, which C declares as an array.
+///
+/// C doesn't check the length of array arguments, so the header notes it.
+fn convert_array_arg(lib: &Library, name: &Ident, array: &TypeArray) -> Option<ConvertArg> {
+    let c_type = c_value_type(lib, &array.elem)?;
+    let len = c_array_len(lib, &array.len)?;
+    let message = format!("argument '{name}' must not be null");
+    Some(ConvertArg {
+        decl: quote! { #name: *const #array, },
+        call: quote! {
+            (if #name.is_null() { panic!(#message) } else { unsafe { #name.read() } }),
+        },
+        c_args: vec![format!("const {c_type} {name}[{len}]")],
+        doc: Some(array_doc(name, &len)),
+        ..Default::default()
+    })
+}
+
+/// Converts `&[T; N]` and `&mut [T; N]` from a pointer to the elements.
+fn convert_array_ref_arg(
+    lib: &Library,
+    name: &Ident,
+    array: &TypeArray,
+    mutable: bool,
+) -> Option<ConvertArg> {
+    let c_type = c_value_type(lib, &array.elem)?;
+    let len = c_array_len(lib, &array.len)?;
+    let message = format!("argument '{name}' must not be null");
+    Some(if mutable {
+        ConvertArg {
+            decl: quote! { #name: *mut #array, },
+            call: quote! { unsafe { #name.as_mut() }.expect(#message), },
+            c_args: vec![format!("{c_type} {name}[{len}]")],
+            doc: Some(array_doc(name, &len)),
+            ..Default::default()
+        }
+    } else {
+        ConvertArg {
+            decl: quote! { #name: *const #array, },
+            call: quote! { unsafe { #name.as_ref() }.expect(#message), },
+            c_args: vec![format!("const {c_type} {name}[{len}]")],
+            doc: Some(array_doc(name, &len)),
+            ..Default::default()
+        }
+    })
+}
+
+/// Describes the length of an array argument, which C doesn't check.
+fn array_doc(name: &Ident, len: &str) -> String {
+    format!("`{name}` must point to at least {len} elements.")
+}
+
+/// Returns the C length of an array type, which is an integer or the name of a constant in the
+/// library.
+fn c_array_len(lib: &Library, len: &Expr) -> Option<String> {
+    match len {
+        Expr::Group(ExprGroup { expr, .. }) | Expr::Paren(ExprParen { expr, .. }) => {
+            c_array_len(lib, expr)
+        }
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) => Some(lit.base10_digits().to_owned()),
+        Expr::Path(ExprPath {
+            qself: None, path, ..
+        }) => {
+            let ident = path.get_ident()?;
+            let constant = lib.constants.iter().find(|c| *ident == c.name)?;
+            Some(constant.c_name(lib))
+        }
         _ => None,
     }
 }
 
-fn convert_array_arg(_name: &Ident, _elem: &Type, _len: &Expr) -> Option<ConvertArg> {
-    todo!("convert_array_arg")
+/// Converts `extern "C" fn` pointers, which C passes directly, from a typedef named after the
+/// argument. `Option<extern "C" fn>` is `nullable`, which the header marks, and otherwise null is
+/// rejected.
+fn convert_fn_ptr(
+    lib: &Library,
+    name: &Ident,
+    bare_fn: &TypeBareFn,
+    nullable: bool,
+    options: &ArgOptions,
+) -> Option<ConvertArg> {
+    if bare_fn.lifetimes.is_some() || bare_fn.variadic.is_some() {
+        return None;
+    }
+    // Only the C calling convention can be called from C.
+    let abi = bare_fn.abi.as_ref()?;
+    if abi.name.as_ref().is_some_and(|abi| abi.value() != "C") {
+        return None;
+    }
+    let inputs: Vec<&Type> = bare_fn.inputs.iter().map(|arg| &arg.ty).collect();
+    let typedef = c_callback_typedef(lib, name, options, &inputs, &bare_fn.output, false)?;
+    let message = format!("argument '{name}' must not be null");
+    let (call, c_arg) = if nullable {
+        (
+            quote! { #name, },
+            format!("{} /* nullable */ {name}", typedef.0),
+        )
+    } else {
+        (
+            quote! { #name.expect(#message), },
+            format!("{} {name}", typedef.0),
+        )
+    };
+    Some(ConvertArg {
+        decl: quote! { #name: Option<#bare_fn>, },
+        call,
+        c_args: vec![c_arg],
+        typedefs: vec![typedef],
+        ..Default::default()
+    })
+}
+
+/// Converts closures like `impl Fn(T) -> U`, `&dyn Fn(T) -> U` or `Box<dyn FnMut(T) -> U>` from
+/// a C function pointer and a `void* user_data` that's passed back to it. `wrap` converts the
+/// closure to the argument type.
+fn convert_closure_arg(
+    lib: &Library,
+    name: &Ident,
+    bounds: &Punctuated<TypeParamBound, Token![+]>,
+    options: &ArgOptions,
+    wrap: impl Fn(TokenStream) -> TokenStream,
+) -> Option<ConvertArg> {
+    let signature = fn_trait_signature(bounds)?;
+    let inputs: Vec<&Type> = signature.inputs.iter().collect();
+    let output = &signature.output;
+    let typedef = c_callback_typedef(lib, name, options, &inputs, output, true)?;
+    let user_data = Ident::new(&format!("{name}_user_data"), Span::call_site());
+    let args: Vec<Ident> = (0..inputs.len())
+        .map(|i| Ident::new(&format!("arg{i}"), Span::call_site()))
+        .collect();
+    let closure = wrap(quote! {
+        move |#(#args: #inputs),*| unsafe { callback(#(#args,)* #user_data) }
+    });
+    let message = format!("argument '{name}' must not be null");
+    Some(ConvertArg {
+        decl: quote! {
+            #name: Option<unsafe extern "C" fn(#(#inputs,)* *mut std::ffi::c_void) #output>,
+            #user_data: *mut std::ffi::c_void,
+        },
+        call: quote! {
+            {
+                let callback = #name.expect(#message);
+                #closure
+            },
+        },
+        c_args: vec![
+            format!("{} {name}", typedef.0),
+            format!("void* {user_data}"),
+        ],
+        typedefs: vec![typedef],
+        ..Default::default()
+    })
+}
+
+/// Returns the arguments and return type of `Fn(T) -> U`, `FnMut(T) -> U` or `FnOnce(T) -> U`,
+/// with no other bounds except lifetimes.
+fn fn_trait_signature(
+    bounds: &Punctuated<TypeParamBound, Token![+]>,
+) -> Option<&ParenthesizedGenericArguments> {
+    let mut signature = None;
+    for bound in bounds {
+        match bound {
+            TypeParamBound::Trait(TraitBound {
+                modifier: TraitBoundModifier::None,
+                lifetimes: None,
+                path,
+                ..
+            }) if signature.is_none() => {
+                let segment = path.segments.last()?;
+                if !["Fn", "FnMut", "FnOnce"].iter().any(|t| segment.ident == t) {
+                    return None;
+                }
+                let PathArguments::Parenthesized(args) = &segment.arguments else {
+                    return None;
+                };
+                signature = Some(args);
+            }
+            TypeParamBound::Lifetime(_) => (),
+            _ => return None,
+        }
+    }
+    signature
+}
+
+/// Returns the name and definition of the C typedef for a callback argument, e.g.
+/// `typedef void (*MyLibLoadProgressFn)(double);` for `progress` of `my_lib_load`, which can have
+/// a trailing `void* user_data`.
+fn c_callback_typedef(
+    lib: &Library,
+    name: &Ident,
+    options: &ArgOptions,
+    inputs: &[&Type],
+    output: &ReturnType,
+    user_data: bool,
+) -> Option<(String, String)> {
+    let mut c_inputs = inputs
+        .iter()
+        .map(|ty| c_value_type(lib, ty))
+        .collect::<Option<Vec<_>>>()?;
+    if user_data {
+        c_inputs.push("void* user_data".to_owned());
+    }
+    if c_inputs.is_empty() {
+        c_inputs.push("void".to_owned());
+    }
+    let c_output = match output {
+        ReturnType::Default => "void".to_owned(),
+        ReturnType::Type(_, ty) if matches!(ty.as_ref(), Type::Tuple(t) if t.elems.is_empty()) => {
+            "void".to_owned()
+        }
+        ReturnType::Type(_, ty) => c_value_type(lib, ty)?,
+    };
+    // Named after the function too, so that unrelated functions can use the same argument name.
+    let function = &options.function;
+    let function = function
+        .strip_prefix(&lib.function_prefix)
+        .unwrap_or(function);
+    let typedef_name = format!(
+        "{}{}{}Fn",
+        lib.type_prefix,
+        function.to_case(Case::Pascal),
+        name.to_string().to_case(Case::Pascal)
+    );
+    let definition = format!(
+        "typedef {c_output} (*{typedef_name})({});",
+        c_inputs.join(", ")
+    );
+    Some((typedef_name, definition))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum StringKind {
+    Str,
+    CStr,
+    String,
 }
 
-fn convert_fn_ptr<'a>(
-    _name: &Ident,
-    _inputs: impl IntoIterator<Item = &'a BareFnArg>,
-    _output: &ReturnType,
-    _abi: Option<&Abi>,
-    _is_unsafe: bool,
+/// Returns the kind of string for `&str`, `&CStr` and `String`.
+fn string_kind(ty: &Type) -> Option<StringKind> {
+    match ty {
+        Type::Group(TypeGroup { elem, .. }) | Type::Paren(TypeParen { elem, .. }) => {
+            string_kind(elem)
+        }
+        Type::Reference(TypeReference {
+            mutability: None,
+            elem,
+            ..
+        }) => match type_name(elem)?.as_str() {
+            "str" => Some(StringKind::Str),
+            "CStr" => Some(StringKind::CStr),
+            _ => None,
+        },
+        _ => (type_name(ty)? == "String").then_some(StringKind::String),
+    }
+}
+
+/// Converts `&str`, `&CStr`, `String` and `Option`s of them, which accept null, from
+/// `const char*`. With `#[coast(len)]`, `&str` and `String` are passed as a pointer and a
+/// length instead, and needn't be NUL-terminated.
+fn convert_string_arg(
+    lib: &Library,
+    name: &Ident,
+    arg_type: &Type,
+    options: &ArgOptions,
 ) -> Option<ConvertArg> {
-    todo!("convert_fn_ptr")
+    let (optional, kind) = match arg_type {
+        Type::Path(TypePath { qself: None, path }) if generic_arg(path, "Option").is_some() => {
+            (true, string_kind(generic_arg(path, "Option")?)?)
+        }
+        _ => (false, string_kind(arg_type)?),
+    };
+    if kind == StringKind::CStr && options.len {
+        return None;
+    }
+    let len = Ident::new(&format!("{name}_len"), Span::call_site());
+    let value = if kind == StringKind::CStr {
+        quote! { unsafe { std::ffi::CStr::from_ptr(#name) } }
+    } else {
+        let bytes = if options.len {
+            quote! { unsafe { std::slice::from_raw_parts(#name.cast::<u8>(), #len) } }
+        } else {
+            quote! { unsafe { std::ffi::CStr::from_ptr(#name) }.to_bytes() }
+        };
+        // Strings are converted to a `Cow<str>`, so that lossy conversion can own its copy.
+        match options.invalid_utf8.unwrap_or(lib.invalid_utf8) {
+            Utf8Policy::Panic => {
+                let message = format!("argument '{name}' must be valid UTF-8");
+                quote! { std::borrow::Cow::Borrowed(std::str::from_utf8(#bytes).expect(#message)) }
+            }
+            Utf8Policy::Error => quote! {
+                std::borrow::Cow::Borrowed(match std::str::from_utf8(#bytes) {
+                    Ok(value) => value,
+                    Err(_) => return coastal_error(),
+                })
+            },
+            Utf8Policy::Lossy => quote! { String::from_utf8_lossy(#bytes) },
+        }
+    };
+    let message = format!("argument '{name}' must not be null");
+    let call = match (optional, kind) {
+        (true, _) => {
+            let value = quote! { (if #name.is_null() { None } else { Some(#value) }) };
+            match kind {
+                StringKind::Str => quote! { #value.as_deref() },
+                StringKind::CStr => value,
+                StringKind::String => quote! { #value.map(std::borrow::Cow::into_owned) },
+            }
+        }
+        (false, StringKind::CStr) => quote! {
+            (if #name.is_null() { panic!(#message) } else { #value })
+        },
+        (false, _) => {
+            // A null pointer with a zero length is an empty string.
+            let on_null = if options.len {
+                quote! {
+                    assert!(#len == 0, #message);
+                    std::borrow::Cow::Borrowed("")
+                }
+            } else {
+                quote! { panic!(#message) }
+            };
+            let value = quote! { (if #name.is_null() { #on_null } else { #value }) };
+            match kind {
+                StringKind::String => quote! { #value.into_owned() },
+                _ => quote! { &*#value },
+            }
+        }
+    };
+    Some(if options.len {
+        ConvertArg {
+            decl: quote! { #name: *const std::ffi::c_char, #len: usize, },
+            call: quote! { #call, },
+            c_args: vec![format!("const char* {name}"), format!("size_t {len}")],
+            ..Default::default()
+        }
+    } else {
+        ConvertArg {
+            decl: quote! { #name: *const std::ffi::c_char, },
+            call: quote! { #call, },
+            c_args: vec![format!("const char* {name}")],
+            ..Default::default()
+        }
+    })
+}
+
+/// Returns the kind of string for `&'static str`, `&'static CStr`, `String` and `CString`,
+/// which can be returned to C.
+fn string_return_kind(ty: &Type) -> Option<StringReturn> {
+    match ty {
+        Type::Group(TypeGroup { elem, .. }) | Type::Paren(TypeParen { elem, .. }) => {
+            string_return_kind(elem)
+        }
+        Type::Reference(TypeReference {
+            lifetime: Some(lifetime),
+            mutability: None,
+            elem,
+            ..
+        }) if lifetime.ident == "static" => match type_name(elem)?.as_str() {
+            "str" => Some(StringReturn::Str),
+            "CStr" => Some(StringReturn::CStr),
+            _ => None,
+        },
+        _ => match type_name(ty)?.as_str() {
+            "String" => Some(StringReturn::String),
+            "CString" => Some(StringReturn::CString),
+            _ => None,
+        },
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum StringReturn {
+    Str,
+    CStr,
+    String,
+    CString,
+}
+
+/// Returns an expression converting `value` to a `CString`, following the policy for NULs.
+fn c_string(lib: &Library, value: TokenStream) -> TokenStream {
+    let message = "returned string must not contain NUL characters";
+    match lib.interior_nul {
+        NulPolicy::Panic => quote! { std::ffi::CString::new(#value).expect(#message) },
+        NulPolicy::Error => quote! {
+            match std::ffi::CString::new(#value) {
+                Ok(value) => value,
+                Err(_) => return coastal_error(),
+            }
+        },
+        NulPolicy::Truncate => quote! {
+            match std::ffi::CString::new(#value) {
+                Ok(value) => value,
+                Err(error) => {
+                    let position = error.nul_position();
+                    let mut bytes = error.into_vec();
+                    bytes.truncate(position);
+                    std::ffi::CString::new(bytes).expect("truncated at the first NUL")
+                }
+            }
+        },
+    }
+}
+
+/// Converts returned strings, and `Option`s of them which return null for `None`.
+///
+/// Owned strings are returned as `char*`, which C frees with `prefix_string_free()`. Static
+/// strings are returned as `const char*`, with `&'static str` copied once to add a NUL.
+fn convert_string_return(lib: &Library, ty: &Type) -> Option<ConvertReturn> {
+    let (optional, kind) = match ty {
+        Type::Path(TypePath { qself: None, path }) if generic_arg(path, "Option").is_some() => {
+            (true, string_return_kind(generic_arg(path, "Option")?)?)
+        }
+        _ => (false, string_return_kind(ty)?),
+    };
+    let value = quote! { value };
+    let pointer = match kind {
+        StringReturn::Str => {
+            let copy = c_string(lib, quote! { #value });
+            quote! {{
+                // Each distinct string is copied once, and kept for the life of the process.
+                static CACHE: std::sync::Mutex<
+                    std::collections::BTreeMap<&'static str, &'static std::ffi::CStr>,
+                > = std::sync::Mutex::new(std::collections::BTreeMap::new());
+                let mut cache = CACHE.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+                match cache.get(#value) {
+                    Some(copy) => copy.as_ptr(),
+                    None => {
+                        let copy: &'static std::ffi::CStr = Box::leak((#copy).into_boxed_c_str());
+                        cache.insert(#value, copy);
+                        copy.as_ptr()
+                    }
+                }
+            }}
+        }
+        StringReturn::CStr => quote! { #value.as_ptr() },
+        StringReturn::String => {
+            let copy = c_string(lib, quote! { #value });
+            quote! { (#copy).into_raw() }
+        }
+        StringReturn::CString => quote! { #value.into_raw() },
+    };
+    let owned = matches!(kind, StringReturn::String | StringReturn::CString);
+    let (return_type, null, c_type) = if owned {
+        (
+            quote! { *mut std::ffi::c_char },
+            quote! { std::ptr::null_mut() },
+            "char*",
+        )
+    } else {
+        (
+            quote! { *const std::ffi::c_char },
+            quote! { std::ptr::null() },
+            "const char*",
+        )
+    };
+    let after = if optional {
+        quote! { ; match ret { Some(value) => #pointer, None => #null } }
+    } else {
+        quote! { ; { let value = ret; #pointer } }
+    };
+    let mut doc = if owned {
+        format!("The string is freed with {}().", lib.string_free_name())
+    } else {
+        "The string is owned by the library.".to_owned()
+    };
+    if lib.interior_nul == NulPolicy::Error
+        && matches!(kind, StringReturn::Str | StringReturn::String)
+    {
+        doc.push_str(" A string containing a NUL character is an error.");
+    }
+    Some(ConvertReturn {
+        before: quote! { let ret = },
+        after,
+        return_type,
+        c_type: c_type.to_owned(),
+        error_value: Some(null),
+        error_doc: Some("NULL".to_owned()),
+        doc: Some(doc),
+        frees_string: owned,
+        ..Default::default()
+    })
 }
 
 /// Converts arguments like `foo: i32` by value.
-fn convert_path_arg(name: &Ident, type_path: &Path) -> Option<ConvertArg> {
-    const NO_CONVERSION: &[(&str, &str, &str)] = &[
-        ("bool", "bool", "bool"),
-        ("i8", "i8", "int8_t"),
-        ("i16", "i16", "int16_t"),
-        ("i32", "i32", "int32_t"),
-        ("i64", "i64", "int64_t"),
-        ("u8", "u8", "uint8_t"),
-        ("u16", "u16", "uint16_t"),
-        ("u32", "u32", "uint32_t"),
-        ("u64", "u64", "uint64_t"),
-        ("usize", "usize", "size_t"),
-        ("isize", "isize", "ssize_t"),
-        ("f32", "f32", "float"),
-        ("f64", "f64", "double"),
-        ("c_char", "std::ffi::c_char", "char"),
-        ("c_uchar", "std::ffi::c_uchar", "unsigned char"),
-        ("c_schar", "std::ffi::c_schar", "signed char"),
-    ];
-    const NON_ZERO: &[(&str, &str, &str)] = &[
-        ("NonZeroI8", "i8", "int8_t"),
-        ("NonZeroI16", "i16", "int16_t"),
-        ("NonZeroI32", "i32", "int32_t"),
-        ("NonZeroI64", "i64", "int64_t"),
-        ("NonZeroU8", "u8", "int8_t"),
-        ("NonZeroU16", "u16", "int16_t"),
-        ("NonZeroU32", "u32", "int32_t"),
-        ("NonZeroU64", "u64", "int64_t"),
-        ("NonZeroIsize", "isize", "ssize_t"),
-        ("NonZeroUsize", "usize", "size_t"),
-    ];
+fn convert_path_arg(
+    lib: &Library,
+    name: &Ident,
+    arg_type: &Type,
+    type_path: &Path,
+    options: &ArgOptions,
+) -> Option<ConvertArg> {
+    if let Some(Type::TraitObject(TypeTraitObject { bounds, .. })) = generic_arg(type_path, "Box") {
+        return convert_closure_arg(lib, name, bounds, options, |closure| {
+            quote! { Box::new(#closure) }
+        });
+    }
+    if let Some(s) = lib.find_struct(arg_type) {
+        if !s.opaque {
+            return Some(ConvertArg {
+                decl: quote! { #name: #type_path, },
+                call: quote! { #name, },
+                c_args: vec![format!("{} {name}", s.c_name(lib))],
+                ..Default::default()
+            });
+        }
+        // Opaque structs taken by value are consumed, so C must not free them afterwards.
+        let message = format!("argument '{name}' must not be null");
+        return Some(ConvertArg {
+            decl: quote! { #name: *mut #type_path, },
+            call: quote! { { assert!(!#name.is_null(), #message); *unsafe { Box::from_raw(#name) } }, },
+            c_args: vec![format!("{}* {name}", s.c_name(lib))],
+            ..Default::default()
+        });
+    }
+    if let Some(e) = lib.find_enum(arg_type) {
+        return convert_enum_arg(lib, name, type_path, e);
+    }
+    if let Some(elem) = generic_arg(type_path, "Box") {
+        let s = lib.find_struct(elem).filter(|s| s.opaque)?;
+        let message = format!("argument '{name}' must not be null");
+        return Some(ConvertArg {
+            decl: quote! { #name: *mut #elem, },
+            call: quote! { { assert!(!#name.is_null(), #message); unsafe { Box::from_raw(#name) } }, },
+            c_args: vec![format!("{}* {name}", s.c_name(lib))],
+            ..Default::default()
+        });
+    }
     let type_string = type_path.into_token_stream().to_string();
     if let Some(r) = NO_CONVERSION
         .iter()
@@ -141,6 +839,7 @@ fn convert_path_arg(name: &Ident, type_path: &Path) -> Option<ConvertArg> {
                 decl: quote! { #name: #path, },
                 call: quote! { #name, },
                 c_args: vec![format!("{c} {name}")],
+                ..Default::default()
             }
         })
     {
@@ -149,16 +848,19 @@ fn convert_path_arg(name: &Ident, type_path: &Path) -> Option<ConvertArg> {
     if let Some(r) = NON_ZERO
         .iter()
         .find(|(n, _, _)| n == &type_string)
-        .map(|(_, r, c)| {
+        .map(|(n, r, c)| {
+            let non_zero = TokenStream::from_str(&format!("std::num::{n}")).unwrap();
             let raw_type = Ident::new(r, Span::call_site());
+            let message = format!("argument '{name}' must be non-zero");
             ConvertArg {
                 decl: quote! {
                     #name: #raw_type,
                 },
                 call: quote! {
-                    #name.try_into().map_err(|_| panic!("argument '#name' must be non-zero")),
+                    #non_zero::new(#name).expect(#message),
                 },
                 c_args: vec![format!("{c} {name}")],
+                ..Default::default()
             }
         })
     {
@@ -167,20 +869,670 @@ fn convert_path_arg(name: &Ident, type_path: &Path) -> Option<ConvertArg> {
     None
 }
 
-fn convert_ptr_arg(_name: &Ident, _path: &Type, _mutable: bool) -> Option<ConvertArg> {
-    todo!("convert_ptr_arg")
+/// Converts `Option<T>` arguments, where `None` is null for references, boxes, `NonNull` and
+/// function pointers, or zero for non-zero integers. Other values are passed as a flag and the
+/// value, or as a pointer to the value if the argument is `nullable`.
+fn convert_option_arg(
+    lib: &Library,
+    name: &Ident,
+    inner: &Type,
+    options: &ArgOptions,
+) -> Option<ConvertArg> {
+    let nullable = options.nullable;
+    if let Type::BareFn(bare_fn) = inner {
+        return convert_fn_ptr(lib, name, bare_fn, true, options);
+    }
+    if let Type::Reference(TypeReference {
+        mutability, elem, ..
+    }) = inner
+    {
+        let c_type = c_pointee_type(lib, elem)?;
+        return Some(if mutability.is_some() {
+            ConvertArg {
+                decl: quote! { #name: *mut #elem, },
+                call: quote! { unsafe { #name.as_mut() }, },
+                c_args: vec![format!("{c_type}* /* nullable */ {name}")],
+                ..Default::default()
+            }
+        } else {
+            ConvertArg {
+                decl: quote! { #name: *const #elem, },
+                call: quote! { unsafe { #name.as_ref() }, },
+                c_args: vec![format!("const {c_type}* /* nullable */ {name}")],
+                ..Default::default()
+            }
+        });
+    }
+    let Type::Path(TypePath { qself: None, path }) = inner else {
+        return None;
+    };
+    if let Some(elem) = generic_arg(path, "Box") {
+        let s = lib.find_struct(elem).filter(|s| s.opaque)?;
+        return Some(ConvertArg {
+            decl: quote! { #name: *mut #elem, },
+            call: quote! { (!#name.is_null()).then(|| unsafe { Box::from_raw(#name) }), },
+            c_args: vec![format!("{}* /* nullable */ {name}", s.c_name(lib))],
+            ..Default::default()
+        });
+    }
+    if let Some(elem) = generic_arg(path, "NonNull") {
+        let c_type = c_pointee_type(lib, elem)?;
+        return Some(ConvertArg {
+            decl: quote! { #name: Option<std::ptr::NonNull<#elem>>, },
+            call: quote! { #name, },
+            c_args: vec![format!("{c_type}* /* nullable */ {name}")],
+            ..Default::default()
+        });
+    }
+    let type_string = path.to_token_stream().to_string();
+    if let Some((n, r, c)) = NON_ZERO.iter().find(|(n, _, _)| *n == type_string) {
+        let non_zero = TokenStream::from_str(&format!("std::num::{n}")).unwrap();
+        let raw_type = Ident::new(r, Span::call_site());
+        return Some(ConvertArg {
+            decl: quote! { #name: #raw_type, },
+            call: quote! { #non_zero::new(#name), },
+            c_args: vec![format!("{c} /* 0 for none */ {name}")],
+            ..Default::default()
+        });
+    }
+    let c_type = c_value_type(lib, inner)?;
+    if nullable {
+        return Some(ConvertArg {
+            decl: quote! { #name: *const #inner, },
+            call: quote! { unsafe { #name.as_ref() }.copied(), },
+            c_args: vec![format!("const {c_type}* /* nullable */ {name}")],
+            ..Default::default()
+        });
+    }
+    let has_name = Ident::new(&format!("has_{name}"), Span::call_site());
+    Some(ConvertArg {
+        decl: quote! { #has_name: bool, #name: #inner, },
+        call: quote! { #has_name.then_some(#name), },
+        c_args: vec![format!("bool {has_name}"), format!("{c_type} {name}")],
+        ..Default::default()
+    })
+}
+
+/// Converts `Option<T>` returns, where `None` is null for references, boxes and `NonNull`, or
+/// zero for non-zero integers. Other values are written to an out-parameter, returning whether
+/// there was one.
+fn convert_option_return(
+    lib: &Library,
+    inner: &Type,
+    options: &ReturnOptions,
+) -> Option<ConvertReturn> {
+    if let Type::Reference(TypeReference {
+        mutability, elem, ..
+    }) = inner
+    {
+        let c_type = c_pointee_type(lib, elem)?;
+        return Some(if mutability.is_some() {
+            ConvertReturn {
+                before: quote! { let ret = },
+                after: quote! { ; ret.map_or(std::ptr::null_mut(), |value| value as *mut #elem) },
+                return_type: quote! { *mut #elem },
+                c_type: format!("{c_type}* /* nullable */"),
+                error_value: Some(quote! { std::ptr::null_mut() }),
+                error_doc: Some("NULL".to_owned()),
+                ..Default::default()
+            }
+        } else {
+            ConvertReturn {
+                before: quote! { let ret = },
+                after: quote! { ; ret.map_or(std::ptr::null(), |value| value as *const #elem) },
+                return_type: quote! { *const #elem },
+                c_type: format!("const {c_type}* /* nullable */"),
+                error_value: Some(quote! { std::ptr::null() }),
+                error_doc: Some("NULL".to_owned()),
+                ..Default::default()
+            }
+        });
+    }
+    let Type::Path(TypePath { qself: None, path }) = inner else {
+        return None;
+    };
+    if let Some(elem) = generic_arg(path, "Box") {
+        let s = lib.find_struct(elem).filter(|s| s.opaque)?;
+        return Some(ConvertReturn {
+            before: quote! { let ret = },
+            after: quote! { ; ret.map_or(std::ptr::null_mut(), Box::into_raw) },
+            return_type: quote! { *mut #elem },
+            c_type: format!("{}* /* nullable */", s.c_name(lib)),
+            error_value: Some(quote! { std::ptr::null_mut() }),
+            error_doc: Some("NULL".to_owned()),
+            ..Default::default()
+        });
+    }
+    if let Some(elem) = generic_arg(path, "NonNull") {
+        let c_type = c_pointee_type(lib, elem)?;
+        return Some(ConvertReturn {
+            before: quote! {},
+            after: quote! {},
+            return_type: quote! { Option<std::ptr::NonNull<#elem>> },
+            c_type: format!("{c_type}* /* nullable */"),
+            error_value: Some(quote! { None }),
+            error_doc: Some("NULL".to_owned()),
+            ..Default::default()
+        });
+    }
+    let type_string = path.to_token_stream().to_string();
+    if let Some((n, r, c)) = NON_ZERO.iter().find(|(n, _, _)| *n == type_string) {
+        let non_zero = TokenStream::from_str(&format!("std::num::{n}")).unwrap();
+        let raw_type = Ident::new(r, Span::call_site());
+        return Some(ConvertReturn {
+            before: quote! { let ret = },
+            after: quote! { ; ret.map_or(0, #non_zero::get) },
+            return_type: quote! { #raw_type },
+            c_type: (*c).to_owned(),
+            error_value: Some(quote! { 0 }),
+            error_doc: Some("0".to_owned()),
+            doc: Some("Returns 0 for none.".to_owned()),
+            ..Default::default()
+        });
+    }
+    let c_type = c_value_type(lib, inner)?;
+    let out = options.out_name("out");
+    Some(ConvertReturn {
+        before: quote! { let ret = },
+        after: quote! {
+            ;
+            match ret {
+                Some(value) => {
+                    if !#out.is_null() {
+                        unsafe { #out.write(value) };
+                    }
+                    true
+                }
+                None => false,
+            }
+        },
+        return_type: quote! { bool },
+        c_type: "bool".to_owned(),
+        decl: quote! { #out: *mut #inner, },
+        error_value: Some(quote! { false }),
+        error_doc: Some("false".to_owned()),
+        c_args: vec![format!("{c_type}* {out}")],
+        doc: Some(format!(
+            "Returns true and writes the value to {out} unless it's NULL, or false for none."
+        )),
+        ..Default::default()
+    })
+}
+
+/// Returns the C type that a reference or pointer to `elem` points to: a struct, a value type or
+/// `void`.
+fn c_pointee_type(lib: &Library, elem: &Type) -> Option<String> {
+    match lib.find_struct(elem) {
+        Some(s) => Some(s.c_name(lib)),
+        None if type_name(elem)? == "c_void" => Some("void".to_owned()),
+        None => c_value_type(lib, elem),
+    }
+}
+
+/// Converts enums from integers or tagged unions, checking that they're valid rather than
+/// transmuting them.
+fn convert_enum_arg(lib: &Library, name: &Ident, type_path: &Path, e: &Enum) -> Option<ConvertArg> {
+    if e.has_fields() {
+        let mirror = Ident::new(&e.name, Span::call_site());
+        let invalid = invalid_enum_value(lib, name, type_path, e, quote! { tag })?;
+        return Some(ConvertArg {
+            decl: quote! { #name: coastal_types::#mirror, },
+            call: quote! {
+                match #name.to_rust() { Ok(value) => value, Err(tag) => #invalid },
+            },
+            c_args: vec![format!("{} {name}", e.c_name(lib))],
+            ..Default::default()
+        });
+    }
+    let raw_type = e.raw_type();
+    let arms = e.variants.iter().map(|variant| {
+        let value = int_literal(variant.value);
+        let variant = Ident::new(&variant.name, Span::call_site());
+        quote! { #value => #type_path::#variant, }
+    });
+    let invalid = invalid_enum_value(lib, name, type_path, e, quote! { #name })?;
+    Some(ConvertArg {
+        decl: quote! { #name: #raw_type, },
+        call: quote! { match #name { #(#arms)* _ => #invalid }, },
+        c_args: vec![format!("{} {name}", e.c_name(lib))],
+        ..Default::default()
+    })
+}
+
+/// Returns the expression used in place of an invalid enum argument, whose value is `value`.
+fn invalid_enum_value(
+    lib: &Library,
+    name: &Ident,
+    type_path: &Path,
+    e: &Enum,
+    value: TokenStream,
+) -> Option<TokenStream> {
+    Some(match e.invalid_policy(lib).ok()? {
+        // Abort directly, since a panic would be handled by the function's panic policy.
+        InvalidEnumPolicy::Abort => {
+            let message = format!(
+                "Coastal: argument '{name}' has invalid value {{}} for '{}', aborting",
+                e.name
+            );
+            quote! {
+                {
+                    eprintln!(#message, #value);
+                    std::process::abort()
+                }
+            }
+        }
+        InvalidEnumPolicy::Error => quote! { return coastal_error() },
+        InvalidEnumPolicy::Default => {
+            let variant = Ident::new(e.default.as_deref()?, Span::call_site());
+            quote! { #type_path::#variant }
+        }
+    })
+}
+
+/// Passes raw pointers like `*const T` through unchanged, which `#[coast]` only allows for
+/// `unsafe fn` or `#[coast(unsafe)]`. They're marked as nullable in the header.
+fn convert_ptr_arg(lib: &Library, name: &Ident, ptr: &TypePtr) -> Option<ConvertArg> {
+    Some(ConvertArg {
+        decl: quote! { #name: #ptr, },
+        call: quote! { #name, },
+        c_args: vec![format!(
+            "{} /* nullable */ {name}",
+            c_pointer_type(lib, ptr)?
+        )],
+        ..Default::default()
+    })
+}
+
+/// Returns the C type of a raw pointer to a value type, an opaque struct, `c_void` or another
+/// raw pointer.
+fn c_pointer_type(lib: &Library, ptr: &TypePtr) -> Option<String> {
+    let elem = match ptr.elem.as_ref() {
+        // `const` goes after a pointer, as in `const char* const*`.
+        Type::Ptr(elem) if ptr.mutability.is_none() => {
+            return Some(format!("{} const*", c_pointer_type(lib, elem)?))
+        }
+        Type::Ptr(elem) => c_pointer_type(lib, elem)?,
+        elem => c_pointee_type(lib, elem)?,
+    };
+    Some(match ptr.mutability {
+        Some(_) => format!("{elem}*"),
+        None => format!("const {elem}*"),
+    })
 }
 
+/// Converts references like `foo: &Foo` from pointers, which must not be null.
 fn convert_ref_arg(
-    _name: &Ident,
+    lib: &Library,
+    name: &Ident,
     _lifetime: Option<&Lifetime>,
-    _elem: &Type,
-    _mutable: bool,
+    elem: &Type,
+    mutable: bool,
+    options: &ArgOptions,
 ) -> Option<ConvertArg> {
-    todo!("convert_ref_arg")
+    match elem {
+        Type::Slice(TypeSlice { elem, .. }) => return convert_slice_arg(lib, name, elem, mutable),
+        Type::Array(array) => return convert_array_ref_arg(lib, name, array, mutable),
+        Type::TraitObject(TypeTraitObject { bounds, .. }) if mutable => {
+            return convert_closure_arg(lib, name, bounds, options, |closure| {
+                quote! { &mut #closure }
+            })
+        }
+        Type::TraitObject(TypeTraitObject { bounds, .. }) => {
+            return convert_closure_arg(lib, name, bounds, options, |closure| {
+                quote! { &#closure }
+            })
+        }
+        _ => (),
+    }
+    let c_type = match lib.find_struct(elem) {
+        Some(s) => s.c_name(lib),
+        None => c_value_type(lib, elem)?,
+    };
+    let message = format!("argument '{name}' must not be null");
+    Some(if mutable {
+        ConvertArg {
+            decl: quote! { #name: *mut #elem, },
+            call: quote! { unsafe { #name.as_mut() }.expect(#message), },
+            c_args: vec![format!("{c_type}* {name}")],
+            ..Default::default()
+        }
+    } else {
+        ConvertArg {
+            decl: quote! { #name: *const #elem, },
+            call: quote! { unsafe { #name.as_ref() }.expect(#message), },
+            c_args: vec![format!("const {c_type}* {name}")],
+            ..Default::default()
+        }
+    })
 }
 
-fn convert_path_return(type_path: &Path) -> Option<ConvertReturn> {
+/// Converts `&[T]` and `&mut [T]` from a pointer and a length, where null is only allowed for an
+/// empty slice.
+fn convert_slice_arg(
+    lib: &Library,
+    name: &Ident,
+    elem: &Type,
+    mutable: bool,
+) -> Option<ConvertArg> {
+    let c_type = c_value_type(lib, elem)?;
+    let len = Ident::new(&format!("{name}_len"), Span::call_site());
+    let message = format!("argument '{name}' must not be null unless '{len}' is 0");
+    Some(if mutable {
+        ConvertArg {
+            decl: quote! { #name: *mut #elem, #len: usize, },
+            call: quote! {
+                if #name.is_null() {
+                    assert!(#len == 0, #message);
+                    &mut []
+                } else {
+                    unsafe { std::slice::from_raw_parts_mut(#name, #len) }
+                },
+            },
+            c_args: vec![format!("{c_type}* {name}"), format!("size_t {len}")],
+            ..Default::default()
+        }
+    } else {
+        ConvertArg {
+            decl: quote! { #name: *const #elem, #len: usize, },
+            call: quote! {
+                if #name.is_null() {
+                    assert!(#len == 0, #message);
+                    &[]
+                } else {
+                    unsafe { std::slice::from_raw_parts(#name, #len) }
+                },
+            },
+            c_args: vec![format!("const {c_type}* {name}"), format!("size_t {len}")],
+            ..Default::default()
+        }
+    })
+}
+
+/// Returns `T` if `path` is `Wrapper<T>`, such as `Box<T>` for `wrapper` = `"Box"`.
+fn generic_arg<'a>(path: &'a Path, wrapper: &str) -> Option<&'a Type> {
+    match generic_args(path, wrapper)?[..] {
+        [ty] => Some(ty),
+        _ => None,
+    }
+}
+
+/// Returns the type arguments if `path` is `Wrapper<T, ...>`.
+fn generic_args<'a>(path: &'a Path, wrapper: &str) -> Option<Vec<&'a Type>> {
+    let segment = path.segments.last()?;
+    if segment.ident != wrapper {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    args.args
+        .iter()
+        .map(|arg| match arg {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Converts `Result<T, E>` to a status code, which is zero on success, writing `T` to a trailing
+/// `out` parameter unless it's null. `E` is converted by `coastal::IntoCError`.
+///
+/// The out-parameter is renamed if an argument is already called `out`.
+fn convert_result_return(
+    lib: &Library,
+    ok: &Type,
+    err: &Type,
+    options: &ReturnOptions,
+) -> Option<ConvertReturn> {
+    let ok = lib
+        .convert_return(
+            &ReturnType::Type(Default::default(), Box::new(ok.clone())),
+            &ReturnOptions::default(),
+        )
+        .ok()?;
+    if !ok.c_args.is_empty() {
+        return None; // nested out-parameters
+    }
+    let codes = match lib.find_enum(err).filter(|e| e.error) {
+        Some(e) => {
+            let names: Vec<_> = e
+                .variants
+                .iter()
+                .map(|v| e.c_variant_name(lib, &v.name))
+                .collect();
+            format!("one of {}", names.join(", "))
+        }
+        None => "a nonzero error code".to_owned(),
+    };
+    let mut codes = format!("or {codes}.");
+    let on_error = if lib.uses_last_error() {
+        let [message_name, ..] = lib.last_error_names();
+        codes.push_str(&format!(" The error is also kept for {message_name}()."));
+        quote! {
+            Err(error) => {
+                let code = ::coastal::IntoCError::error_code(&error);
+                coastal_set_last_error(code, error.to_string());
+                code
+            }
+        }
+    } else {
+        quote! { Err(error) => ::coastal::IntoCError::error_code(&error), }
+    };
+    if ok.c_type == "void" {
+        return Some(ConvertReturn {
+            before: quote! { let ret = },
+            after: quote! { ; match ret { Ok(()) => 0, #on_error } },
+            return_type: quote! { std::ffi::c_int },
+            c_type: "int".to_owned(),
+            error_value: Some(quote! { -1 }),
+            error_doc: Some("-1".to_owned()),
+            doc: Some(format!("Returns 0 on success, {codes}")),
+            ..Default::default()
+        });
+    }
+    let (ok_before, ok_after, ok_type) = (ok.before, ok.after, ok.return_type);
+    let out = options.out_name("out");
+    Some(ConvertReturn {
+        before: quote! { let ret = },
+        after: quote! {
+            ;
+            match ret {
+                Ok(value) => {
+                    if !#out.is_null() {
+                        unsafe { #out.write({ #ok_before value #ok_after }) };
+                    }
+                    0
+                }
+                #on_error
+            }
+        },
+        return_type: quote! { std::ffi::c_int },
+        c_type: "int".to_owned(),
+        decl: quote! { #out: *mut #ok_type, },
+        c_args: vec![format!("{}* {out}", ok.c_type)],
+        error_value: Some(quote! { -1 }),
+        error_doc: Some("-1".to_owned()),
+        doc: Some(format!(
+            "Returns 0 on success, writing the result to {out} unless it's NULL, {codes}{}",
+            ok.doc.map(|doc| format!(" {doc}")).unwrap_or_default()
+        )),
+        frees_string: ok.frees_string,
+        slice: ok.slice,
+    })
+}
+
+/// Converts tuples to an out-parameter for each element, named by `#[coast(out = "...")]` or
+/// `out_0`, `out_1`, ..., which are only written if they aren't null. The default names are
+/// renamed if they clash with an argument, while `#[coast]` rejects clashing names it's given.
+fn convert_tuple_return(
+    lib: &Library,
+    tuple: &TypeTuple,
+    options: &ReturnOptions,
+) -> Option<ConvertReturn> {
+    let names: Vec<String> = if options.out.is_empty() {
+        (0..tuple.elems.len())
+            .map(|i| options.out_name(&format!("out_{i}")).to_string())
+            .collect()
+    } else if options.out.len() == tuple.elems.len() {
+        options.out.clone()
+    } else {
+        return None;
+    };
+    let mut decl = TokenStream::new();
+    let mut c_args = Vec::new();
+    let mut writes = TokenStream::new();
+    let mut values = Vec::new();
+    let mut frees_string = false;
+    for (i, (elem, name)) in tuple.elems.iter().zip(&names).enumerate() {
+        let ret = lib
+            .convert_return(
+                &ReturnType::Type(Default::default(), Box::new(elem.clone())),
+                &ReturnOptions::default(),
+            )
+            .ok()?;
+        if !ret.c_args.is_empty() || ret.c_type == "void" || ret.slice.is_some() {
+            return None; // nested out-parameters, or nothing to write
+        }
+        let name = Ident::new(name, Span::call_site());
+        let value = Ident::new(&format!("value_{i}"), Span::call_site());
+        let (before, after, return_type) = (ret.before, ret.after, ret.return_type);
+        decl.extend(quote! { #name: *mut #return_type, });
+        c_args.push(format!("{}* {name}", ret.c_type));
+        // Values aren't converted for null out-parameters, so that they aren't leaked.
+        writes.extend(quote! {
+            if !#name.is_null() {
+                unsafe { #name.write({ #before #value #after }) };
+            }
+        });
+        values.push(value);
+        frees_string |= ret.frees_string;
+    }
+    let doc = match &names[..] {
+        [name] => format!("Writes the result to {name} unless it's NULL."),
+        [names @ .., last] => format!(
+            "Writes the results to {} and {last} unless they're NULL.",
+            names.join(", ")
+        ),
+        [] => return None,
+    };
+    Some(ConvertReturn {
+        before: quote! { let ret = },
+        after: quote! {
+            ;
+            let (#(#values,)*) = ret;
+            #writes
+        },
+        return_type: quote! { () },
+        c_type: "void".to_owned(),
+        decl,
+        c_args,
+        doc: Some(doc),
+        frees_string,
+        ..Default::default()
+    })
+}
+
+/// Converts `Vec<T>` and `Box<[T]>` to a struct of a pointer and a length, which C frees with
+/// the generated function. `boxed` converts `ret` to `Box<[T]>`.
+fn convert_slice_return(lib: &Library, elem: &Type, boxed: TokenStream) -> Option<ConvertReturn> {
+    let slice = SliceType::new(lib, elem)?;
+    let name = slice.rust_name();
+    Some(ConvertReturn {
+        before: quote! { let ret = },
+        after: quote! { ; coastal_types::#name::from_boxed(#boxed) },
+        return_type: quote! { coastal_types::#name },
+        c_type: slice.c_name(lib),
+        error_value: Some(quote! { coastal_types::#name::from_boxed(Box::new([])) }),
+        error_doc: Some(format!("an empty {}", slice.c_name(lib))),
+        doc: Some(format!(
+            "The slice is freed with {}().",
+            slice.free_name(lib)
+        )),
+        slice: Some(slice),
+        ..Default::default()
+    })
+}
+
+fn convert_path_return(
+    lib: &Library,
+    ty: &Type,
+    type_path: &Path,
+    options: &ReturnOptions,
+) -> Option<ConvertReturn> {
+    if let Some([ok, err]) = generic_args(type_path, "Result").as_deref() {
+        return convert_result_return(lib, ok, err, options);
+    }
+    if let Some(inner) = generic_arg(type_path, "Option") {
+        return convert_option_return(lib, inner, options);
+    }
+    if let Some(elem) = generic_arg(type_path, "Vec") {
+        return convert_slice_return(lib, elem, quote! { ret.into_boxed_slice() });
+    }
+    if let Some(Type::Slice(TypeSlice { elem, .. })) = generic_arg(type_path, "Box") {
+        return convert_slice_return(lib, elem, quote! { ret });
+    }
+    if let Some(elem) = generic_arg(type_path, "Box") {
+        // Boxed opaque structs are owned by C until they're freed.
+        let s = lib.find_struct(elem).filter(|s| s.opaque)?;
+        return Some(ConvertReturn {
+            before: quote! { let ret = },
+            after: quote! { ; Box::into_raw(ret) },
+            return_type: quote! { *mut #elem },
+            c_type: format!("{}*", s.c_name(lib)),
+            error_value: Some(quote! { std::ptr::null_mut() }),
+            error_doc: Some("NULL".to_owned()),
+            ..Default::default()
+        });
+    }
+    if let Some(e) = lib.find_enum(ty) {
+        if e.has_fields() {
+            let mirror = Ident::new(&e.name, Span::call_site());
+            return Some(ConvertReturn {
+                before: quote! { let ret = },
+                after: quote! { ; coastal_types::#mirror::from_rust(ret) },
+                return_type: quote! { coastal_types::#mirror },
+                c_type: e.c_name(lib),
+                // The fields are all C values, which are valid when zeroed.
+                error_value: Some(quote! { unsafe { std::mem::zeroed() } }),
+                error_doc: Some(format!("a zeroed {}", e.c_name(lib))),
+                ..Default::default()
+            });
+        }
+        let raw_type = e.raw_type();
+        return Some(ConvertReturn {
+            before: quote! {},
+            after: quote! { as #raw_type },
+            return_type: raw_type,
+            c_type: e.c_name(lib),
+            error_value: Some(quote! { 0 }),
+            error_doc: Some("0".to_owned()),
+            ..Default::default()
+        });
+    }
+    if let Some(s) = lib.find_struct(ty) {
+        if s.opaque {
+            // Opaque structs returned by value are boxed, and owned by C until they're freed.
+            return Some(ConvertReturn {
+                before: quote! { let ret = },
+                after: quote! { ; Box::into_raw(Box::new(ret)) },
+                return_type: quote! { *mut #type_path },
+                c_type: format!("{}*", s.c_name(lib)),
+                error_value: Some(quote! { std::ptr::null_mut() }),
+                error_doc: Some("NULL".to_owned()),
+                ..Default::default()
+            });
+        }
+        return Some(ConvertReturn {
+            before: quote! {},
+            after: quote! {},
+            return_type: type_path.to_token_stream(),
+            c_type: s.c_name(lib),
+            // The fields are all C values, which are valid when zeroed.
+            error_value: Some(quote! { unsafe { std::mem::zeroed() } }),
+            error_doc: Some(format!("a zeroed {}", s.c_name(lib))),
+            ..Default::default()
+        });
+    }
     const ACCEPT: &[(&str, &str, &str)] = &[
         ("bool", "bool", "bool"),
         ("i8", "i8", "int8_t"),
@@ -188,26 +1540,26 @@ fn convert_path_return(type_path: &Path) -> Option<ConvertReturn> {
         ("i32", "i32", "int32_t"),
         ("i64", "i64", "int64_t"),
         ("u8", "u8", "uint8_t"),
-        ("u16", "u16", "uint12_t"),
+        ("u16", "u16", "uint16_t"),
         ("u32", "u32", "uint32_t"),
         ("u64", "u64", "uint64_t"),
         ("usize", "usize", "size_t"),
-        ("isize", "isize", "ssize_t"),
+        ("isize", "isize", "intptr_t"),
         ("f32", "f32", "float"),
         ("f64", "f64", "double"),
-        ("c_char", "c_char", "char"),
-        ("c_uchar", "c_uchar", "unsigned char"),
-        ("c_schar", "c_schar", "signed char"),
+        ("c_char", "std::ffi::c_char", "char"),
+        ("c_uchar", "std::ffi::c_uchar", "unsigned char"),
+        ("c_schar", "std::ffi::c_schar", "signed char"),
         ("NonZeroI8", "i8", "int8_t"),
         ("NonZeroI16", "i16", "int16_t"),
         ("NonZeroI32", "i32", "int32_t"),
         ("NonZeroI64", "i64", "int64_t"),
-        ("NonZeroU8", "u8", "unt8_t"),
-        ("NonZeroU16", "u16", "unt16_t"),
-        ("NonZeroU32", "u32", "unt32_t"),
-        ("NonZeroU64", "u64", "unt64_t"),
+        ("NonZeroU8", "u8", "uint8_t"),
+        ("NonZeroU16", "u16", "uint16_t"),
+        ("NonZeroU32", "u32", "uint32_t"),
+        ("NonZeroU64", "u64", "uint64_t"),
         ("NonZeroUsize", "usize", "size_t"),
-        ("NonZeroIsize", "isize", "ssize_t"),
+        ("NonZeroIsize", "isize", "intptr_t"),
     ];
     let type_string = type_path.into_token_stream().to_string();
     ACCEPT
@@ -215,12 +1567,15 @@ fn convert_path_return(type_path: &Path) -> Option<ConvertReturn> {
         .find(|(n, _, _)| n == &type_string)
         .map(|(n, r, c)| ConvertReturn {
             before: quote! {},
-            after: if n == r {
-                quote! {}
-            } else {
+            after: if n.starts_with("NonZero") {
                 quote! { .into() }
+            } else {
+                quote! {}
             },
             return_type: TokenStream::from_str(r).unwrap(),
             c_type: (*c).to_owned(),
+            error_value: Some(quote! { Default::default() }),
+            error_doc: Some(if *n == "bool" { "false" } else { "0" }.to_owned()),
+            ..Default::default()
         })
 }